@@ -0,0 +1,189 @@
+// いつもの値からのずれ（z スコア）で判定する
+//
+// - メトリクスごと・時（0〜23時）ごとに EWMA で平均と分散を覚える
+// - warm_up_samples 件たまるまでの時間帯は閾値の判定のまま
+// - 判定してから覚える（外れ値が自分の平均を引き上げる前に判定するため）
+// - 覚えるのは起動中に取れた値だけ（履歴ファイルからは覚えない）
+// - 覚えた値はアプリのデータディレクトリの anomaly_baseline.json に置く
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::config::schema::AnomalyConfig;
+use crate::ui::types::{level_by_threshold, AlertLevel, AlertThresholds, Policy};
+
+pub const BASELINE_FILE: &str = "anomaly_baseline.json";
+const HOURS: usize = 24;
+// ほとんど動かないメトリクスで z が跳ね上がらないように
+const MIN_STD: f64 = 1.0;
+
+/// 指数移動平均での平均と分散
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Ewma {
+    pub mean: f64,
+    pub var: f64,
+    pub count: u64,
+}
+
+impl Ewma {
+    pub fn update(&mut self, x: f64, alpha: f64) {
+        if self.count == 0 {
+            self.mean = x;
+            self.var = 0.0;
+        } else {
+            let diff = x - self.mean;
+            let incr = alpha * diff;
+            self.mean += incr;
+            self.var = (1.0 - alpha) * (self.var + diff * incr);
+        }
+        self.count += 1;
+    }
+
+    pub fn z_score(&self, x: f64) -> f64 {
+        (x - self.mean) / self.var.sqrt().max(MIN_STD)
+    }
+}
+
+/// 覚えた平均より上にずれているほど深刻（下にずれるのは Safe）
+pub struct AnomalyPolicy {
+    pub baseline: Ewma,
+    pub z: AlertThresholds,
+}
+
+impl Policy<f64> for AnomalyPolicy {
+    fn level(&self, value: &f64) -> AlertLevel {
+        level_by_threshold(self.baseline.z_score(*value) as f32, self.z)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Baseline {
+    // メトリクス名 → 0〜23時の24個
+    pub metrics: BTreeMap<String, Vec<Ewma>>,
+}
+
+impl Baseline {
+    /// 無い・読めないときは空から覚え直す
+    pub fn load(path: &Path) -> Self {
+        let Ok(text) = std::fs::read_to_string(path) else { return Self::default(); };
+        serde_json::from_str(&text).unwrap_or_else(|e| {
+            eprintln!("{}: {}", path.display(), e);
+            Self::default()
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let json = serde_json::to_string(self).map_err(std::io::Error::other)?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, json)?;
+        std::fs::rename(&tmp, path)
+    }
+
+    /// 学習が済んだ時間帯ならレベルを返し、そのあと value を覚える
+    pub fn observe(&mut self, metric: &str, hour: usize, value: f64, cfg: &AnomalyConfig) -> Option<AlertLevel> {
+        let buckets = self.metrics.entry(metric.to_string()).or_insert_with(|| vec![Ewma::default(); HOURS]);
+        buckets.resize(HOURS, Ewma::default());
+        let bucket = &mut buckets[hour % HOURS];
+
+        let level = (bucket.count >= cfg.warm_up_samples)
+            .then(|| AnomalyPolicy { baseline: *bucket, z: cfg.z }.level(&value));
+        bucket.update(value, cfg.alpha);
+        level
+    }
+}
+
+/// サンプラーが覚える基準値と保存先。終了時に保存できるよう app にも持たせる
+#[derive(Clone, Default)]
+pub struct SharedBaseline {
+    path: Option<PathBuf>,
+    baseline: Arc<Mutex<Baseline>>,
+    // 定期保存と終了時の保存が同じファイルに重ねて書かないように
+    write_lock: Arc<Mutex<()>>,
+}
+
+impl SharedBaseline {
+    /// path が None なら保存しない（CLI）
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let baseline = path.as_deref().map(Baseline::load).unwrap_or_default();
+        Self { path, baseline: Arc::new(Mutex::new(baseline)), write_lock: Arc::default() }
+    }
+
+    pub fn has_path(&self) -> bool {
+        self.path.is_some()
+    }
+
+    pub fn observe(&self, metric: &str, hour: usize, value: f64, cfg: &AnomalyConfig) -> Option<AlertLevel> {
+        self.baseline.lock().unwrap().observe(metric, hour, value, cfg)
+    }
+
+    /// ファイルに書く。書いている間も observe は止めない
+    pub fn save_blocking(&self) {
+        let Some(path) = &self.path else { return; };
+        let snapshot = self.baseline.lock().unwrap().clone();
+        let _guard = self.write_lock.lock().unwrap();
+        if let Err(e) = snapshot.save(path) {
+            eprintln!("failed to save {}: {}", path.display(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cfg() -> AnomalyConfig {
+        AnomalyConfig { enabled: true, warm_up_samples: 100, alpha: 0.05, ..AnomalyConfig::default() }
+    }
+
+    #[test]
+    fn learns_mean_and_variance() {
+        let mut e = Ewma::default();
+        for i in 0..2000 {
+            e.update(if i % 2 == 0 { 40.0 } else { 60.0 }, 0.01);
+        }
+        assert!((e.mean - 50.0).abs() < 1.0);
+        assert!((e.var.sqrt() - 10.0).abs() < 1.0);
+        assert!((e.z_score(80.0) - 3.0).abs() < 0.3);
+    }
+
+    #[test]
+    fn classifies_by_z_score_after_warm_up() {
+        let mut baseline = Baseline::default();
+        // ビルドサーバーのように 60% 前後が普通
+        for i in 0..100 {
+            let v = 60.0 + (i % 5) as f64 * 2.0 - 4.0;
+            assert_eq!(baseline.observe("cpu", 14, v, &cfg()), None);
+        }
+        assert_eq!(baseline.observe("cpu", 14, 62.0, &cfg()), Some(AlertLevel::Safe));
+        assert_eq!(baseline.observe("cpu", 14, 99.0, &cfg()), Some(AlertLevel::Critical));
+        // 別の時間帯はまだ学習中
+        assert_eq!(baseline.observe("cpu", 3, 99.0, &cfg()), None);
+    }
+
+    #[test]
+    fn survives_a_round_trip_through_disk() {
+        let path = std::env::temp_dir().join(format!("vital-monitor-baseline-{}.json", std::process::id()));
+        let mut baseline = Baseline::default();
+        baseline.observe("mem", 9, 42.0, &cfg());
+        baseline.save(&path).unwrap();
+        assert_eq!(Baseline::load(&path), baseline);
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(Baseline::load(&path), Baseline::default());
+    }
+
+    #[test]
+    fn shared_baseline_saves_what_it_learned() {
+        let path = std::env::temp_dir().join(format!("vital-monitor-shared-baseline-{}.json", std::process::id()));
+        let shared = SharedBaseline::load(Some(path.clone()));
+        shared.observe("cpu", 10, 55.0, &cfg());
+        shared.save_blocking();
+
+        let reloaded = SharedBaseline::load(Some(path.clone()));
+        assert_eq!(*reloaded.baseline.lock().unwrap(), *shared.baseline.lock().unwrap());
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,123 @@
+// 1回分のスナップショットに、閾値と履歴の両方からレベルを付ける
+//
+// トレイ・webview・外部出力はここで付けたレベルを使う（UpdateFeed 経由）
+use chrono::Timelike;
+
+use crate::alerts::anomaly::SharedBaseline;
+use crate::alerts::rules::CompiledRules;
+use crate::metrics::history::{History, Sample};
+use crate::metrics::types::MetricsSnapshot;
+use crate::ui::types::{AlertLevel, AlertThresholds, ClassifiedSnapshot, MetricKind, ProcessMetric, TrayConfig, GB};
+
+// 学習した基準値を書き出す間隔
+const BASELINE_SAVE_MS: u64 = 5 * 60 * 1000;
+
+/// サンプラーと CLI が1つずつ持つ。ルールは設定が変わったときだけパースし直す
+#[derive(Default)]
+pub struct Evaluator {
+    rules: CompiledRules,
+    baseline: SharedBaseline,
+    baseline_saved_ms: u64,
+}
+
+impl Evaluator {
+    /// 前回までに学習した基準値の続きから覚える
+    pub fn with_baseline(baseline: SharedBaseline) -> Self {
+        Self { baseline, ..Self::default() }
+    }
+
+    /// history には sample を積んだ後のものを渡す
+    pub fn evaluate(&mut self, cfg: &TrayConfig, history: &History, sample: &Sample) -> ClassifiedSnapshot {
+        if !self.rules.is_compiled_from(&cfg.alerts.rules) {
+            self.rules = CompiledRules::compile(&cfg.alerts.rules);
+        }
+        let mut classified = evaluate(cfg, history, sample);
+        if cfg.anomaly.enabled {
+            self.apply_anomaly(cfg, sample.ts_ms, &mut classified);
+        }
+        classified.derived.extend(self.rules.evaluate(history, sample));
+        classified
+    }
+
+    /// 学習が済んだ時間帯では、閾値と継続時間（*_for_secs）で付けたレベルを z スコアのレベルで置き換える
+    /// z の *_for_secs は使わない（その時点の値だけで決まる）
+    fn apply_anomaly(&mut self, cfg: &TrayConfig, ts_ms: u64, classified: &mut ClassifiedSnapshot) {
+        let hour = chrono::DateTime::from_timestamp_millis(ts_ms as i64)
+            .map(|t| t.with_timezone(&chrono::Local).hour() as usize)
+            .unwrap_or(0);
+        let a = &cfg.anomaly;
+        for kind in &a.metrics {
+            let metric = match kind {
+                MetricKind::Cpu => classified.cpu.as_mut().map(|m| (m.value.0 as f64, &mut m.level)),
+                MetricKind::Mem => classified.mem.as_mut().map(|m| (m.value.0 as f64, &mut m.level)),
+                MetricKind::Nw => classified.nw.as_mut().map(|m| (m.value.0 as f64, &mut m.level)),
+            };
+            let Some((value, level)) = metric else { continue; };
+            if let Some(adaptive) = self.baseline.observe(kind.key(), hour, value, a) {
+                *level = adaptive;
+            }
+        }
+    }
+
+    /// 保存する頃合いなら基準値を返す。書き込みは呼び出し側が履歴のロックを外してから行う
+    pub fn baseline_to_save(&mut self, ts_ms: u64) -> Option<SharedBaseline> {
+        if !self.baseline.has_path() || ts_ms.saturating_sub(self.baseline_saved_ms) < BASELINE_SAVE_MS {
+            return None;
+        }
+        self.baseline_saved_ms = ts_ms;
+        Some(self.baseline.clone())
+    }
+}
+
+/// 閾値・継続時間・傾きまで（ルールは Evaluator で足す）
+pub fn evaluate(cfg: &TrayConfig, history: &History, sample: &Sample) -> ClassifiedSnapshot {
+    let mut classified = ClassifiedSnapshot::new(sample.snapshot.clone(), &cfg.policies());
+
+    let t = &cfg.thresholds;
+    let ts_ms = sample.ts_ms;
+    if let Some(m) = classified.cpu.as_mut() {
+        apply_durations(&mut m.level, &t.cpu, history, ts_ms, |s| s.cpu_pct.map(f64::from));
+    }
+    if let Some(m) = classified.mem.as_mut() {
+        apply_durations(&mut m.level, &t.mem, history, ts_ms, |s| s.mem_pressure_pct.map(f64::from));
+    }
+    if let Some(m) = classified.nw.as_mut() {
+        apply_durations(&mut m.level, &t.nw, history, ts_ms, |s| s.nw_ms);
+    }
+
+    // 設定から外したばかりのプロセスは落とす
+    for sample in &sample.snapshot.procs {
+        let Some(watch) = cfg.processes.iter().find(|w| w.name == sample.name) else { continue; };
+        let mut p = ProcessMetric::classify(sample, watch);
+        if let Some(m) = p.cpu.as_mut() {
+            apply_durations(&mut m.level, &watch.cpu, history, ts_ms, |s| {
+                s.procs.iter().find(|p| p.name == sample.name).and_then(|p| p.cpu_pct).map(f64::from)
+            });
+        }
+        if let Some(m) = p.rss_gb.as_mut() {
+            apply_durations(&mut m.level, &watch.rss_gb, history, ts_ms, |s| {
+                s.procs.iter().find(|p| p.name == sample.name).and_then(|p| p.rss_bytes).map(|b| b as f64 / GB)
+            });
+        }
+        classified.procs.push(p);
+    }
+
+    classified.derived.extend(super::trend::evaluate(&cfg.trends, history, ts_ms));
+    classified
+}
+
+// 継続秒数の指定が無いメトリクスは閾値だけのレベルのまま
+fn apply_durations(
+    level: &mut AlertLevel,
+    t: &AlertThresholds,
+    history: &History,
+    ts_ms: u64,
+    value: impl Fn(&MetricsSnapshot) -> Option<f64>,
+) {
+    let secs = t.longest_for_secs();
+    if secs == 0 {
+        return;
+    }
+    let series = history.series(ts_ms, secs, value);
+    *level = super::sustained::sustained_level(t, &series, ts_ms);
+}
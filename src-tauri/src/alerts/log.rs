@@ -0,0 +1,226 @@
+// レベルの遷移の記録と「確認済み」の管理
+//
+// - 遷移はアプリのデータディレクトリの alert_log.jsonl に1行ずつ追記する
+// - 確認済みのメトリクスは、確認したときのレベルまではトレイのアイコンを上げない
+//   それより上がったとき・Warning 未満に戻ったときに確認は外れる
+use std::collections::{BTreeMap, VecDeque};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::alerts::transitions::Transition;
+use crate::ui::types::{AlertLevel, ClassifiedSnapshot};
+
+pub const LOG_FILE: &str = "alert_log.jsonl";
+// 手元に持つ件数（ファイルは読み込み時と、この2倍の行数を超えたときにこの件数まで切り詰める）
+const MAX_ENTRIES: usize = 500;
+const MAX_FILE_LINES: usize = MAX_ENTRIES * 2;
+
+#[derive(Debug, Default)]
+pub struct AlertLog {
+    entries: VecDeque<Transition>,
+    // Warning 以上のままのメトリクス → そこに上がったときの遷移
+    active: BTreeMap<String, Transition>,
+    // 確認済みのメトリクス → 確認したときのレベル
+    acked: BTreeMap<String, AlertLevel>,
+    path: Option<PathBuf>,
+    // ファイルの今の行数（追記するたびに数える）
+    file_lines: usize,
+}
+
+impl AlertLog {
+    /// 読めない行は飛ばす。None ならファイルには書かない
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let mut log = Self { path, ..Self::default() };
+        let Some(path) = log.path.clone() else { return log; };
+        let Ok(text) = std::fs::read_to_string(&path) else { return log; };
+
+        let lines: Vec<&str> = text.lines().collect();
+        for line in &lines {
+            if let Ok(t) = serde_json::from_str::<Transition>(line) {
+                log.push(t);
+            }
+        }
+        log.file_lines = lines.len();
+        if lines.len() > MAX_ENTRIES {
+            log.compact(&path);
+        }
+        log
+    }
+
+    pub fn record(&mut self, t: &Transition) {
+        if t.level >= AlertLevel::Warning {
+            self.active.insert(t.metric.clone(), t.clone());
+            if self.acked.get(&t.metric).is_some_and(|acked| t.level > *acked) {
+                self.acked.remove(&t.metric);
+            }
+        } else {
+            self.active.remove(&t.metric);
+            self.acked.remove(&t.metric);
+        }
+
+        self.push(t.clone());
+        let Some(path) = self.path.clone() else { return; };
+        match append(&path, t) {
+            Ok(()) => self.file_lines += 1,
+            Err(e) => eprintln!("failed to write {}: {}", path.display(), e),
+        }
+        if self.file_lines > MAX_FILE_LINES {
+            self.compact(&path);
+        }
+    }
+
+    fn compact(&mut self, path: &Path) {
+        match self.rewrite(path) {
+            Ok(()) => self.file_lines = self.entries.len(),
+            Err(e) => eprintln!("failed to compact {}: {}", path.display(), e),
+        }
+    }
+
+    fn push(&mut self, t: Transition) {
+        if self.entries.len() == MAX_ENTRIES {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(t);
+    }
+
+    /// 新しい順に最大 n 件
+    pub fn recent(&self, n: usize) -> Vec<Transition> {
+        self.entries.iter().rev().take(n).cloned().collect()
+    }
+
+    /// Warning 以上のままのものと、確認済みかどうか
+    pub fn active(&self) -> Vec<(Transition, bool)> {
+        self.active
+            .values()
+            .map(|t| (t.clone(), self.acked.contains_key(&t.metric)))
+            .collect()
+    }
+
+    pub fn acknowledge(&mut self, metric: &str) -> bool {
+        let Some(t) = self.active.get(metric) else { return false; };
+        self.acked.insert(metric.to_string(), t.level);
+        true
+    }
+
+    pub fn acknowledge_all(&mut self) {
+        for (metric, t) in &self.active {
+            self.acked.insert(metric.clone(), t.level);
+        }
+    }
+
+    /// 記録だけ消す（Warning 以上のままのものと確認済みはそのまま）
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        if let Some(path) = self.path.clone() {
+            match self.rewrite(&path) {
+                Ok(()) => self.file_lines = 0,
+                Err(e) => eprintln!("failed to clear {}: {}", path.display(), e),
+            }
+        }
+    }
+
+    /// 確認済みのメトリクスは Normal として表示させる
+    pub fn apply_acks(&self, s: &mut ClassifiedSnapshot) {
+        for (metric, acked) in &self.acked {
+            if let Some(level) = s.level_mut(metric) {
+                if *level <= *acked {
+                    *level = (*level).min(AlertLevel::Normal);
+                }
+            }
+        }
+    }
+
+    fn rewrite(&self, path: &Path) -> std::io::Result<()> {
+        let mut text = String::new();
+        for t in &self.entries {
+            text.push_str(&serde_json::to_string(t).map_err(std::io::Error::other)?);
+            text.push('\n');
+        }
+        let tmp = path.with_extension("jsonl.tmp");
+        std::fs::write(&tmp, text)?;
+        std::fs::rename(&tmp, path)
+    }
+}
+
+fn append(path: &Path, t: &Transition) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut line = serde_json::to_string(t).map_err(std::io::Error::other)?;
+    line.push('\n');
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(line.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::types::MetricsSnapshot;
+    use crate::ui::types::Policies;
+
+    fn cpu(value: f32) -> ClassifiedSnapshot {
+        let snap = MetricsSnapshot { cpu_pct: Some(value), ..MetricsSnapshot::default() };
+        ClassifiedSnapshot::new(snap, &Policies::default())
+    }
+
+    #[test]
+    fn acknowledgement_holds_until_escalation_or_clear() {
+        let mut log = AlertLog::default();
+        log.record(&Transition::sample(Some(AlertLevel::Safe), AlertLevel::Warning).at(1));
+        assert!(log.acknowledge("cpu"));
+        assert!(!log.acknowledge("mem"));
+
+        let mut s = cpu(80.0);
+        log.apply_acks(&mut s);
+        assert_eq!(s.cpu.unwrap().level, AlertLevel::Normal);
+
+        // 確認したレベルより上がったら確認は外れる
+        log.record(&Transition::sample(Some(AlertLevel::Warning), AlertLevel::Critical).at(2));
+        let mut s = cpu(95.0);
+        log.apply_acks(&mut s);
+        assert_eq!(s.cpu.unwrap().level, AlertLevel::Critical);
+        assert_eq!(log.active(), vec![(Transition::sample(Some(AlertLevel::Warning), AlertLevel::Critical).at(2), false)]);
+
+        // 戻ったら一覧からも消える
+        log.acknowledge_all();
+        log.record(&Transition::sample(Some(AlertLevel::Critical), AlertLevel::Safe).at(3));
+        assert!(log.active().is_empty());
+        assert_eq!(log.recent(2).iter().map(|t| t.ts_ms).collect::<Vec<_>>(), vec![3, 2]);
+    }
+
+    #[test]
+    fn persists_transitions_as_json_lines() {
+        let path = std::env::temp_dir().join(format!("vital-monitor-alert-log-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let mut log = AlertLog::load(Some(path.clone()));
+        log.record(&Transition::sample(Some(AlertLevel::Safe), AlertLevel::Critical).at(1));
+        log.record(&Transition::sample(Some(AlertLevel::Critical), AlertLevel::Normal).at(2));
+
+        let reloaded = AlertLog::load(Some(path.clone()));
+        assert_eq!(reloaded.recent(10), log.recent(10));
+
+        log.clear();
+        assert!(AlertLog::load(Some(path.clone())).recent(10).is_empty());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn compacts_the_file_while_running() {
+        let path = std::env::temp_dir().join(format!("vital-monitor-alert-log-compact-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let lines = || std::fs::read_to_string(&path).unwrap().lines().count();
+
+        let mut log = AlertLog::load(Some(path.clone()));
+        for i in 0..MAX_FILE_LINES as u64 {
+            log.record(&Transition::sample(Some(AlertLevel::Safe), AlertLevel::Warning).at(i));
+        }
+        assert_eq!(lines(), MAX_FILE_LINES);
+
+        // 超えたところで MAX_ENTRIES 件に切り詰め、新しいものは残る
+        log.record(&Transition::sample(Some(AlertLevel::Warning), AlertLevel::Safe).at(9999));
+        assert_eq!(lines(), MAX_ENTRIES);
+        assert_eq!(AlertLog::load(Some(path.clone())).recent(1)[0].ts_ms, 9999);
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,81 @@
+// レベルの変化（遷移）を検出して通知先に配る
+pub mod anomaly;
+pub mod evaluate;
+pub mod log;
+pub mod quiet;
+pub mod rules;
+pub mod sustained;
+pub mod transitions;
+pub mod trend;
+pub mod webhook;
+
+use std::sync::Mutex;
+use tauri::Manager;
+
+use crate::config::schema::NotificationConfig;
+use crate::ipc::UpdateFeed;
+use crate::ui::types::TrayUiState;
+use log::AlertLog;
+use quiet::{DoNotDisturb, MuteGate};
+use transitions::{Transition, TransitionTracker};
+
+pub fn spawn_alert_engine(app: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut feed = app.state::<UpdateFeed>().subscribe();
+        let mut tracker = TransitionTracker::default();
+        let mut webhooks = webhook::Dispatcher::default();
+        let mut gate = MuteGate::default();
+
+        while feed.changed().await.is_ok() {
+            let Some(update) = feed.borrow_and_update().clone() else { continue; };
+            let cfg = app.state::<TrayUiState>().config.lock().unwrap().alerts.clone();
+            webhooks.configure(&cfg.webhooks);
+
+            let state = (
+                app.state::<DoNotDisturb>().until(),
+                quiet::in_quiet_hours(&cfg.quiet.windows, chrono::Local::now().time()),
+            );
+            // 静かな間もトレイと記録はふだんどおり。通知と Webhook だけ止める
+            let transitions = tracker.observe(update.ts_ms, &update.classified);
+            for transition in &transitions {
+                app.state::<Mutex<AlertLog>>().lock().unwrap().record(transition);
+            }
+
+            let routed = gate.route(state, cfg.quiet.digest, &transitions);
+            if routed.state_changed {
+                crate::ui::tray::refresh_dnd_menu(&app);
+            }
+            if !routed.released.is_empty() {
+                notify_digest(&cfg.notifications, &routed.released);
+                for transition in &routed.released {
+                    webhooks.send(transition);
+                }
+            }
+            for transition in &routed.deliver {
+                notify_desktop(&cfg.notifications, transition);
+                webhooks.send(transition);
+            }
+            if !transitions.is_empty() {
+                crate::ui::tray::refresh_alerts_menu(&app);
+            }
+        }
+    });
+}
+
+fn notify_desktop(cfg: &NotificationConfig, t: &Transition) {
+    let escalated = t.previous.is_none_or(|prev| prev < t.level);
+    if !cfg.enabled || !escalated || t.level < cfg.min_level {
+        return;
+    }
+    let body = webhook::render_message("{icon} {metric} is {level} ({value}, was {previous})", "", t);
+    crate::ui::notify::notify(&format!("Vital Monitor: {}", t.label), &body);
+}
+
+/// 静かな間にまとめておいた変化を1通で知らせる
+fn notify_digest(cfg: &NotificationConfig, held: &[Transition]) {
+    if !cfg.enabled {
+        return;
+    }
+    let title = format!("Vital Monitor: {} alert change(s) while muted", held.len());
+    crate::ui::notify::notify(&title, &quiet::digest_text(held));
+}
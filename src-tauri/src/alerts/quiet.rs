@@ -0,0 +1,212 @@
+// 静かにする時間帯（設定の quiet_hours）と「しばらく通知しない」（トレイの Do Not Disturb）
+//
+// その間もトレイのアイコンと記録はふだんどおり。通知と Webhook だけ止め、
+// digest が有効なら明けたときにメトリクスごとにまとめて送る
+use chrono::{Duration, Local, NaiveTime, TimeZone};
+use std::sync::Mutex;
+
+use crate::alerts::transitions::Transition;
+use crate::config::schema::QuietWindow;
+use crate::metrics::history::now_ms;
+
+/// Do Not Disturb の終わる時刻（UNIX epoch ミリ秒）
+#[derive(Default)]
+pub struct DoNotDisturb(pub Mutex<Option<u64>>);
+
+impl DoNotDisturb {
+    pub fn until(&self) -> Option<u64> {
+        let mut until = self.0.lock().unwrap();
+        // 過ぎていれば片付ける
+        if until.is_some_and(|t| t <= now_ms()) {
+            *until = None;
+        }
+        *until
+    }
+
+    pub fn set(&self, until: Option<u64>) {
+        *self.0.lock().unwrap() = until;
+    }
+}
+
+/// 明日の 0:00（ローカル時刻）
+pub fn tomorrow_ms() -> u64 {
+    let midnight = (Local::now().date_naive() + Duration::days(1)).and_time(NaiveTime::MIN);
+    Local
+        .from_local_datetime(&midnight)
+        .earliest()
+        .map(|t| t.timestamp_millis() as u64)
+        .unwrap_or_else(|| now_ms() + 24 * 60 * 60 * 1000)
+}
+
+/// "22:00" 形式
+pub fn parse_time(s: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(s.trim(), "%H:%M").ok()
+}
+
+/// start > end なら日付をまたぐ（例: 22:00〜07:00）。start は含み end は含まない
+pub fn in_window(w: &QuietWindow, now: NaiveTime) -> bool {
+    let (Some(start), Some(end)) = (parse_time(&w.start), parse_time(&w.end)) else { return false; };
+    if start <= end {
+        start <= now && now < end
+    } else {
+        now >= start || now < end
+    }
+}
+
+pub fn in_quiet_hours(windows: &[QuietWindow], now: NaiveTime) -> bool {
+    windows.iter().any(|w| in_window(w, now))
+}
+
+/// 静かな間の遷移をメトリクスごとに1件にまとめておく
+#[derive(Debug, Default)]
+pub struct Digest {
+    pending: Vec<Transition>,
+}
+
+impl Digest {
+    /// previous は静かになる前のレベルのまま残す
+    pub fn hold(&mut self, t: &Transition) {
+        match self.pending.iter_mut().find(|p| p.metric == t.metric) {
+            Some(p) => *p = Transition { previous: p.previous, ..t.clone() },
+            None => self.pending.push(t.clone()),
+        }
+    }
+
+    /// 元のレベルに戻っているものは落とす
+    pub fn take(&mut self) -> Vec<Transition> {
+        std::mem::take(&mut self.pending)
+            .into_iter()
+            .filter(|t| t.previous != Some(t.level))
+            .collect()
+    }
+
+    pub fn clear(&mut self) {
+        self.pending.clear();
+    }
+}
+
+/// (Do Not Disturb の終わり, 静かな時間帯か)
+pub type MuteState = (Option<u64>, bool);
+
+/// 静かにするかどうかと digest をまとめて扱う。通知やメニューには触らない
+#[derive(Debug, Default)]
+pub struct MuteGate {
+    state: MuteState,
+    digest: Digest,
+}
+
+/// 1回分の遷移の振り分け
+#[derive(Debug, Default, PartialEq)]
+pub struct Routed {
+    /// Do Not Disturb のメニューを直す
+    pub state_changed: bool,
+    /// 静かな間にためていた分（1通にまとめて知らせる）
+    pub released: Vec<Transition>,
+    /// ふだんどおり通知・Webhook に送る分
+    pub deliver: Vec<Transition>,
+}
+
+impl MuteGate {
+    /// 静かな間は keep_digest ならためておき、明けた最初の回に released で返す
+    pub fn route(&mut self, state: MuteState, keep_digest: bool, transitions: &[Transition]) -> Routed {
+        let muted = state.0.is_some() || state.1;
+        let state_changed = state != self.state;
+        self.state = state;
+
+        let released = if muted { Vec::new() } else { self.digest.take() };
+        let deliver = if muted {
+            if keep_digest {
+                transitions.iter().for_each(|t| self.digest.hold(t));
+            }
+            Vec::new()
+        } else {
+            transitions.to_vec()
+        };
+        if !keep_digest {
+            self.digest.clear();
+        }
+        Routed { state_changed, released, deliver }
+    }
+}
+
+/// 通知センターに出す1通分の本文
+pub fn digest_text(transitions: &[Transition]) -> String {
+    transitions
+        .iter()
+        .map(|t| format!("{} {:?} ({})", t.label, t.level, t.value_text()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::types::AlertLevel;
+
+    fn window(start: &str, end: &str) -> QuietWindow {
+        QuietWindow { start: start.to_string(), end: end.to_string() }
+    }
+
+    fn at(s: &str) -> NaiveTime {
+        parse_time(s).unwrap()
+    }
+
+    #[test]
+    fn windows_can_cross_midnight() {
+        let night = window("22:00", "07:00");
+        assert!(in_window(&night, at("23:30")));
+        assert!(in_window(&night, at("02:00")));
+        assert!(!in_window(&night, at("07:00")));
+        assert!(!in_window(&night, at("12:00")));
+
+        let lunch = window("12:00", "13:00");
+        assert!(in_quiet_hours(&[night, lunch], at("12:30")));
+        assert!(!in_quiet_hours(&[window("bad", "07:00")], at("02:00")));
+    }
+
+    #[test]
+    fn digest_keeps_net_changes_per_metric() {
+        let mut digest = Digest::default();
+        digest.hold(&Transition::sample(Some(AlertLevel::Safe), AlertLevel::Warning).of("cpu"));
+        digest.hold(&Transition::sample(Some(AlertLevel::Warning), AlertLevel::Critical).of("cpu"));
+        digest.hold(&Transition::sample(Some(AlertLevel::Normal), AlertLevel::Warning).of("mem"));
+        digest.hold(&Transition::sample(Some(AlertLevel::Warning), AlertLevel::Normal).of("mem"));
+
+        let sent = digest.take();
+        assert_eq!(sent.len(), 1);
+        assert_eq!((sent[0].previous, sent[0].level), (Some(AlertLevel::Safe), AlertLevel::Critical));
+        assert_eq!(digest_text(&sent), "CPU Critical (95%)");
+        assert!(digest.take().is_empty());
+    }
+
+    #[test]
+    fn releases_the_digest_when_unmuted() {
+        let mut gate = MuteGate::default();
+        let up = Transition::sample(Some(AlertLevel::Safe), AlertLevel::Warning);
+        let worse = Transition::sample(Some(AlertLevel::Warning), AlertLevel::Critical);
+
+        let routed = gate.route((Some(10), false), true, std::slice::from_ref(&up));
+        assert_eq!(routed, Routed { state_changed: true, ..Routed::default() });
+        let routed = gate.route((None, true), true, std::slice::from_ref(&worse));
+        assert_eq!(routed, Routed { state_changed: true, ..Routed::default() });
+
+        // 明けた回に、ためていた分を1件にまとめて返し、その回の遷移はふだんどおり送る
+        let down = Transition::sample(Some(AlertLevel::Critical), AlertLevel::Normal).at(2);
+        let routed = gate.route((None, false), true, std::slice::from_ref(&down));
+        assert!(routed.state_changed);
+        assert_eq!(routed.released.len(), 1);
+        assert_eq!((routed.released[0].previous, routed.released[0].level), (Some(AlertLevel::Safe), AlertLevel::Critical));
+        assert_eq!(routed.deliver, vec![down]);
+
+        assert_eq!(gate.route((None, false), true, &[]), Routed::default());
+    }
+
+    #[test]
+    fn drops_held_transitions_when_digest_is_off() {
+        let mut gate = MuteGate::default();
+        let up = Transition::sample(Some(AlertLevel::Safe), AlertLevel::Critical);
+        gate.route((None, true), true, std::slice::from_ref(&up));
+        gate.route((None, true), false, &[]);
+        assert!(gate.route((None, false), true, &[]).released.is_empty());
+    }
+}
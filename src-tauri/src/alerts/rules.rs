@@ -0,0 +1,419 @@
+//! 複数の条件を組み合わせたアラートルール
+//!
+//! 例: `"cpu > 85 for 2m AND nw_p95 >= 200"` / `"(mem > 90 OR disk > 95) for 300"`
+//! - メトリクス名          cpu / mem / nw / nw_p95 / disk
+//! - 比較                  `>` `>=` `<` `<=` `==` `!=` と数値
+//! - `AND` / `OR`          `&&` / `||` でも可。AND の方が強く結びつく
+//! - `条件 for 期間`       直近の履歴でずっと成り立っていること。期間は `300` `300s` `5m` `1h`（履歴の長さまで）
+//! - `( … )`               まとめる
+//!
+//! 値が取れていないメトリクスとの比較は成り立たない扱い
+
+use std::fmt;
+
+use crate::alerts::sustained::SLACK_MS;
+use crate::config::schema::RuleConfig;
+use crate::metrics::history::{History, Sample, HISTORY_CAPACITY};
+use crate::metrics::types::MetricsSnapshot;
+use crate::ui::types::{AlertLevel, DerivedAlert};
+
+// ルールで使える名前
+pub const METRIC_NAMES: [&str; 5] = ["cpu", "mem", "nw", "nw_p95", "disk"];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RuleError {
+    Empty,
+    UnexpectedChar(char, usize),
+    UnexpectedToken(String, usize),
+    UnexpectedEnd,
+    UnknownMetric(String),
+    InvalidNumber(String),
+    InvalidDuration(String),
+    // for の中に for があると履歴を二重に舐めることになるので受け付けない
+    NestedFor(usize),
+}
+
+impl fmt::Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuleError::Empty => write!(f, "empty expression"),
+            RuleError::UnexpectedChar(c, pos) => write!(f, "unexpected '{}' at {}", c, pos),
+            RuleError::UnexpectedToken(t, pos) => write!(f, "unexpected '{}' at {}", t, pos),
+            RuleError::UnexpectedEnd => write!(f, "unexpected end of expression"),
+            RuleError::UnknownMetric(m) => {
+                write!(f, "unknown metric '{}' (expected one of {})", m, METRIC_NAMES.join(", "))
+            }
+            RuleError::InvalidNumber(n) => write!(f, "invalid number '{}'", n),
+            RuleError::InvalidDuration(d) => write!(f, "invalid duration '{}'", d),
+            RuleError::NestedFor(pos) => write!(f, "'for' at {} applies to an expression that already uses 'for'", pos),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompareOp { Gt, Ge, Lt, Le, Eq, Ne }
+
+impl CompareOp {
+    fn symbol(self) -> &'static str {
+        match self {
+            CompareOp::Gt => ">",
+            CompareOp::Ge => ">=",
+            CompareOp::Lt => "<",
+            CompareOp::Le => "<=",
+            CompareOp::Eq => "==",
+            CompareOp::Ne => "!=",
+        }
+    }
+
+    fn holds(self, lhs: f64, rhs: f64) -> bool {
+        match self {
+            CompareOp::Gt => lhs > rhs,
+            CompareOp::Ge => lhs >= rhs,
+            CompareOp::Lt => lhs < rhs,
+            CompareOp::Le => lhs <= rhs,
+            CompareOp::Eq => lhs == rhs,
+            CompareOp::Ne => lhs != rhs,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    Compare { metric: String, op: CompareOp, value: f64 },
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+    For { inner: Box<Expr>, secs: u64 },
+}
+
+impl Expr {
+    pub fn parse(src: &str) -> Result<Self, RuleError> {
+        let tokens = tokenize(src)?;
+        if tokens.is_empty() {
+            return Err(RuleError::Empty);
+        }
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.or()?;
+        match parser.tokens.get(parser.pos) {
+            Some((tok, at)) => Err(RuleError::UnexpectedToken(tok.to_string(), *at)),
+            None => Ok(expr),
+        }
+    }
+
+    fn has_for(&self) -> bool {
+        match self {
+            Expr::Compare { .. } => false,
+            Expr::And(items) | Expr::Or(items) => items.iter().any(Expr::has_for),
+            Expr::For { .. } => true,
+        }
+    }
+
+    /// sample の時点で成り立つか。`for` はその時点から遡った履歴で見る
+    pub fn holds(&self, history: &History, sample: &Sample) -> bool {
+        match self {
+            Expr::Compare { metric, op, value } => {
+                metric_value(metric, &sample.snapshot).is_some_and(|v| op.holds(v, *value))
+            }
+            Expr::And(items) => items.iter().all(|e| e.holds(history, sample)),
+            Expr::Or(items) => items.iter().any(|e| e.holds(history, sample)),
+            Expr::For { inner, secs } => {
+                let Some(from) = secs.checked_mul(1000).and_then(|ms| sample.ts_ms.checked_sub(ms)) else { return false; };
+                let mut window = history.samples_between(from, sample.ts_ms).peekable();
+                let covered = window.peek().is_some_and(|first| first.ts_ms <= from + SLACK_MS);
+                covered && window.all(|s| inner.holds(history, s))
+            }
+        }
+    }
+}
+
+pub fn metric_value(name: &str, s: &MetricsSnapshot) -> Option<f64> {
+    match name {
+        "cpu" => s.cpu_pct.map(f64::from),
+        "mem" => s.mem_pressure_pct.map(f64::from),
+        "nw" => s.nw_ms,
+        "nw_p95" => s.nw_p95_ms,
+        "disk" => s.disk_used_pct.map(f64::from),
+        _ => None,
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(f64),
+    // 秒。範囲は for の後で確かめる
+    Duration(f64),
+    Op(CompareOp),
+    And,
+    Or,
+    For,
+    LParen,
+    RParen,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Ident(name) => write!(f, "{}", name),
+            Token::Number(n) => write!(f, "{}", n),
+            Token::Duration(secs) => write!(f, "{}s", secs),
+            Token::Op(op) => write!(f, "{}", op.symbol()),
+            Token::And => write!(f, "AND"),
+            Token::Or => write!(f, "OR"),
+            Token::For => write!(f, "for"),
+            Token::LParen => write!(f, "("),
+            Token::RParen => write!(f, ")"),
+        }
+    }
+}
+
+fn tokenize(src: &str) -> Result<Vec<(Token, usize)>, RuleError> {
+    let chars: Vec<char> = src.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        let next = chars.get(i + 1).copied();
+        let token = match (c, next) {
+            _ if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            ('(', _) => { i += 1; Token::LParen }
+            (')', _) => { i += 1; Token::RParen }
+            ('&', Some('&')) => { i += 2; Token::And }
+            ('|', Some('|')) => { i += 2; Token::Or }
+            ('>', Some('=')) => { i += 2; Token::Op(CompareOp::Ge) }
+            ('<', Some('=')) => { i += 2; Token::Op(CompareOp::Le) }
+            ('=', Some('=')) => { i += 2; Token::Op(CompareOp::Eq) }
+            ('!', Some('=')) => { i += 2; Token::Op(CompareOp::Ne) }
+            ('>', _) => { i += 1; Token::Op(CompareOp::Gt) }
+            ('<', _) => { i += 1; Token::Op(CompareOp::Lt) }
+            _ if c.is_ascii_digit() || c == '.' => {
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                let number: String = chars[start..i].iter().collect();
+                let suffix_start = i;
+                while i < chars.len() && chars[i].is_ascii_alphabetic() {
+                    i += 1;
+                }
+                let suffix: String = chars[suffix_start..i].iter().collect();
+                let value: f64 = number.parse().map_err(|_| RuleError::InvalidNumber(number.clone()))?;
+                let unit = match suffix.as_str() {
+                    "" => None,
+                    "s" => Some(1.0),
+                    "m" => Some(60.0),
+                    "h" => Some(3600.0),
+                    _ => return Err(RuleError::InvalidDuration(format!("{}{}", number, suffix))),
+                };
+                match unit {
+                    Some(unit) => Token::Duration(value * unit),
+                    None => Token::Number(value),
+                }
+            }
+            _ if c.is_ascii_alphabetic() || c == '_' => {
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+                match word.to_ascii_lowercase().as_str() {
+                    "and" => Token::And,
+                    "or" => Token::Or,
+                    "for" => Token::For,
+                    _ => Token::Ident(word),
+                }
+            }
+            _ => return Err(RuleError::UnexpectedChar(c, start)),
+        };
+        tokens.push((token, start));
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Result<(Token, usize), RuleError> {
+        let tok = self.tokens.get(self.pos).cloned().ok_or(RuleError::UnexpectedEnd)?;
+        self.pos += 1;
+        Ok(tok)
+    }
+
+    fn eat(&mut self, expected: &Token) -> bool {
+        let found = self.tokens.get(self.pos).is_some_and(|(tok, _)| tok == expected);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    // or := and (OR and)*
+    fn or(&mut self) -> Result<Expr, RuleError> {
+        let mut items = vec![self.and()?];
+        while self.eat(&Token::Or) {
+            items.push(self.and()?);
+        }
+        Ok(if items.len() == 1 { items.remove(0) } else { Expr::Or(items) })
+    }
+
+    // and := term (AND term)*
+    fn and(&mut self) -> Result<Expr, RuleError> {
+        let mut items = vec![self.term()?];
+        while self.eat(&Token::And) {
+            items.push(self.term()?);
+        }
+        Ok(if items.len() == 1 { items.remove(0) } else { Expr::And(items) })
+    }
+
+    // term := atom [for 期間]
+    fn term(&mut self) -> Result<Expr, RuleError> {
+        let atom = self.atom()?;
+        if !self.eat(&Token::For) {
+            return Ok(atom);
+        }
+        if atom.has_for() {
+            return Err(RuleError::NestedFor(self.tokens[self.pos - 1].1));
+        }
+        let (secs, text) = match self.next()? {
+            (tok @ Token::Duration(secs), _) => (secs.round(), tok.to_string()),
+            (Token::Number(n), _) => (n.round(), n.to_string()),
+            (tok, at) => return Err(RuleError::UnexpectedToken(tok.to_string(), at)),
+        };
+        // 履歴に残っている以上の長さは確かめられない
+        if !(1.0..=HISTORY_CAPACITY as f64).contains(&secs) {
+            return Err(RuleError::InvalidDuration(text));
+        }
+        Ok(Expr::For { inner: Box::new(atom), secs: secs as u64 })
+    }
+
+    // atom := ( or ) | メトリクス 比較 数値
+    fn atom(&mut self) -> Result<Expr, RuleError> {
+        match self.next()? {
+            (Token::LParen, _) => {
+                let inner = self.or()?;
+                match self.next()? {
+                    (Token::RParen, _) => Ok(inner),
+                    (tok, at) => Err(RuleError::UnexpectedToken(tok.to_string(), at)),
+                }
+            }
+            (Token::Ident(metric), _) => {
+                if !METRIC_NAMES.contains(&metric.as_str()) {
+                    return Err(RuleError::UnknownMetric(metric));
+                }
+                let op = match self.next()? {
+                    (Token::Op(op), _) => op,
+                    (tok, at) => return Err(RuleError::UnexpectedToken(tok.to_string(), at)),
+                };
+                let value = match self.next()? {
+                    (Token::Number(n), _) => n,
+                    (tok, at) => return Err(RuleError::UnexpectedToken(tok.to_string(), at)),
+                };
+                Ok(Expr::Compare { metric, op, value })
+            }
+            (tok, at) => Err(RuleError::UnexpectedToken(tok.to_string(), at)),
+        }
+    }
+}
+
+/// 設定のルールをパース済みの状態で持っておく（パースできないものは飛ばす）
+#[derive(Default)]
+pub struct CompiledRules {
+    source: Vec<RuleConfig>,
+    rules: Vec<(RuleConfig, Expr)>,
+}
+
+impl CompiledRules {
+    pub fn compile(src: &[RuleConfig]) -> Self {
+        Self {
+            source: src.to_vec(),
+            rules: src
+                .iter()
+                .filter_map(|rule| Expr::parse(&rule.when).ok().map(|expr| (rule.clone(), expr)))
+                .collect(),
+        }
+    }
+
+    pub fn is_compiled_from(&self, src: &[RuleConfig]) -> bool {
+        self.source == src
+    }
+
+    /// ルールごとに1件。成り立っていれば設定のレベル、そうでなければ Safe
+    pub fn evaluate(&self, history: &History, sample: &Sample) -> Vec<DerivedAlert> {
+        self.rules
+            .iter()
+            .map(|(rule, expr)| DerivedAlert {
+                name: rule.name.clone(),
+                label: rule.name.clone(),
+                value: None,
+                unit: String::new(),
+                level: if expr.holds(history, sample) { rule.level } else { AlertLevel::Safe },
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(ts_ms: u64, cpu: f32, nw: Option<f64>) -> Sample {
+        let snapshot = MetricsSnapshot { cpu_pct: Some(cpu), nw_ms: nw, ..MetricsSnapshot::default() };
+        Sample { ts_ms, snapshot }
+    }
+
+    #[test]
+    fn parses_precedence_and_durations() {
+        let expr = Expr::parse("cpu > 80 and nw >= 200 || (mem>90) for 5m").unwrap();
+        let cmp = |metric: &str, op, value| Expr::Compare { metric: metric.to_string(), op, value };
+        assert_eq!(
+            expr,
+            Expr::Or(vec![
+                Expr::And(vec![cmp("cpu", CompareOp::Gt, 80.0), cmp("nw", CompareOp::Ge, 200.0)]),
+                Expr::For { inner: Box::new(cmp("mem", CompareOp::Gt, 90.0)), secs: 300 },
+            ])
+        );
+    }
+
+    #[test]
+    fn reports_parse_errors() {
+        assert_eq!(Expr::parse("  "), Err(RuleError::Empty));
+        assert_eq!(Expr::parse("temp > 80"), Err(RuleError::UnknownMetric("temp".to_string())));
+        assert_eq!(Expr::parse("cpu > 80 for 5d"), Err(RuleError::InvalidDuration("5d".to_string())));
+        assert_eq!(Expr::parse("cpu > 80 for 0"), Err(RuleError::InvalidDuration("0".to_string())));
+        // 履歴の長さ（1時間）を超える期間や、u64 に収まらない期間
+        assert!(Expr::parse("cpu > 80 for 1h").is_ok());
+        assert_eq!(Expr::parse("cpu > 80 for 2h"), Err(RuleError::InvalidDuration("7200s".to_string())));
+        assert!(matches!(Expr::parse("cpu > 80 for 99999999999999999999h"), Err(RuleError::InvalidDuration(_))));
+        assert!(matches!(Expr::parse("cpu > 80 for 18446744073709551615"), Err(RuleError::InvalidDuration(_))));
+        assert_eq!(Expr::parse("cpu > 80 AND"), Err(RuleError::UnexpectedEnd));
+        assert_eq!(Expr::parse("cpu > 80)"), Err(RuleError::UnexpectedToken(")".to_string(), 8)));
+        assert_eq!(Expr::parse("cpu = 80"), Err(RuleError::UnexpectedChar('=', 4)));
+        // for の入れ子は評価が履歴の長さの2乗になる
+        assert_eq!(Expr::parse("(cpu > 80 for 1m and nw > 200) for 1h"), Err(RuleError::NestedFor(31)));
+        assert!(Expr::parse("cpu > 80 for 1m and (nw > 200 for 1h)").is_ok());
+    }
+
+    #[test]
+    fn evaluates_against_history() {
+        let mut history = History::new(100);
+        for i in 0..=10 {
+            history.push(sample(i * 1000, if i < 3 { 10.0 } else { 95.0 }, Some(300.0)));
+        }
+        let now = sample(10_000, 95.0, Some(300.0));
+
+        assert!(Expr::parse("cpu > 90 AND nw > 200").unwrap().holds(&history, &now));
+        // 3秒目からなので 7秒は続いているが 10秒は続いていない
+        assert!(Expr::parse("cpu > 90 for 7s").unwrap().holds(&history, &now));
+        assert!(!Expr::parse("cpu > 90 for 10").unwrap().holds(&history, &now));
+        // 値の無いメトリクスは成り立たない
+        assert!(!Expr::parse("disk < 50").unwrap().holds(&history, &now));
+
+        let rules = vec![RuleConfig { name: "bad_link".to_string(), when: "nw > 200 and cpu < 50".to_string(), level: AlertLevel::Critical }];
+        let alerts = CompiledRules::compile(&rules).evaluate(&history, &now);
+        assert_eq!((alerts[0].name.as_str(), alerts[0].level), ("bad_link", AlertLevel::Safe));
+    }
+}
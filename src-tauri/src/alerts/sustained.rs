@@ -0,0 +1,61 @@
+// 「一定時間続いたら」の条件（AlertThresholds の *_for_secs）
+//
+// 深刻なレベルから順に、直近 for_secs 秒の値がすべて下限以上ならそのレベルにする
+// for_secs が 0 のレベルは今の値だけで決まる。履歴が for_secs 分たまるまではそのレベルにならない
+use crate::ui::types::{AlertLevel, AlertThresholds};
+
+// サンプルの間隔ぶんのずれは許す（alerts::rules の for でも使う）
+pub const SLACK_MS: u64 = 1500;
+
+/// series は ts_ms までの (時刻, 値) を古い順に。最後の点が今の値
+pub fn sustained_level(t: &AlertThresholds, series: &[(u64, f64)], ts_ms: u64) -> AlertLevel {
+    for (level, limit, secs) in t.levels() {
+        let Some(from) = secs.checked_mul(1000).and_then(|ms| ts_ms.checked_sub(ms)) else { continue; };
+        let window: Vec<&(u64, f64)> = match series.last() {
+            Some(last) if secs == 0 => vec![last],
+            _ => series.iter().filter(|(ts, _)| *ts >= from).collect(),
+        };
+        let Some((first_ts, _)) = window.first() else { continue; };
+        let covered = secs == 0 || *first_ts <= from + SLACK_MS;
+        if covered && window.iter().all(|(_, v)| *v >= limit as f64) {
+            return level;
+        }
+    }
+    AlertLevel::Safe
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 1秒ごとの値（最後が今）
+    fn series(values: &[f64]) -> (Vec<(u64, f64)>, u64) {
+        let points: Vec<(u64, f64)> = values.iter().enumerate().map(|(i, v)| (i as u64 * 1000, *v)).collect();
+        let now = points.last().unwrap().0;
+        (points, now)
+    }
+
+    #[test]
+    fn short_spikes_do_not_reach_sustained_levels() {
+        // Critical は 5秒続いたら。Warning はすぐ
+        let t = AlertThresholds { critical_for_secs: 5, ..AlertThresholds::new(50.0, 75.0, 90.0) };
+
+        let (spike, now) = series(&[10.0, 10.0, 10.0, 10.0, 10.0, 100.0, 100.0]);
+        assert_eq!(sustained_level(&t, &spike, now), AlertLevel::Warning);
+
+        let (held, now) = series(&[10.0, 95.0, 95.0, 92.0, 99.0, 95.0, 91.0]);
+        assert_eq!(sustained_level(&t, &held, now), AlertLevel::Critical);
+
+        // 一度でも下回れば数え直し
+        let (dip, now) = series(&[95.0, 95.0, 95.0, 80.0, 95.0, 95.0, 95.0]);
+        assert_eq!(sustained_level(&t, &dip, now), AlertLevel::Warning);
+    }
+
+    #[test]
+    fn needs_enough_history_before_escalating() {
+        let t = AlertThresholds { warning_for_secs: 10, critical_for_secs: 10, ..AlertThresholds::new(50.0, 75.0, 90.0) };
+        let (young, now) = series(&[95.0, 95.0, 95.0]);
+        assert_eq!(sustained_level(&t, &young, now), AlertLevel::Normal);
+        assert_eq!(sustained_level(&t, &[], 0), AlertLevel::Safe);
+    }
+}
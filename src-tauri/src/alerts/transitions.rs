@@ -0,0 +1,154 @@
+// メトリクスごとに前回のレベルを覚えておき、変わったときだけ Transition を出す
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::ui::types::{AlertLevel, ClassifiedSnapshot, MetricKind};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Transition {
+    pub ts_ms: u64,
+    // "cpu" / "mem" / "nw"（MetricKind::key）、"proc:<name>:rss" など、派生アラートの name
+    pub metric: String,
+    // 通知に出す名前（CPU / Mem / NW / ルール名など）
+    pub label: String,
+    // 複合ルールは値を持たない
+    pub value: Option<f64>,
+    pub unit: String,
+    // 起動後はじめて値が取れたときは None
+    pub previous: Option<AlertLevel>,
+    pub level: AlertLevel,
+}
+
+impl Transition {
+    pub fn value_text(&self) -> String {
+        match self.value {
+            // RSS（GB）のような小さい値は小数1桁まで出す
+            Some(v) if v.abs() < 10.0 && v.fract() != 0.0 => format!("{:.1}{}", v, self.unit),
+            Some(v) => format!("{:.0}{}", v, self.unit),
+            None => "-".to_string(),
+        }
+    }
+}
+
+/// テスト用の遷移（CPU 95%）。at / of で時刻とメトリクスを変える
+#[cfg(test)]
+impl Transition {
+    pub fn sample(previous: Option<AlertLevel>, level: AlertLevel) -> Self {
+        Self {
+            ts_ms: 1,
+            metric: "cpu".to_string(),
+            label: "CPU".to_string(),
+            value: Some(95.0),
+            unit: "%".to_string(),
+            previous,
+            level,
+        }
+    }
+
+    pub fn at(self, ts_ms: u64) -> Self {
+        Self { ts_ms, ..self }
+    }
+
+    pub fn of(self, metric: &str) -> Self {
+        Self { metric: metric.to_string(), label: metric.to_uppercase(), ..self }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct TransitionTracker {
+    last: HashMap<String, AlertLevel>,
+}
+
+impl TransitionTracker {
+    pub fn observe(&mut self, ts_ms: u64, s: &ClassifiedSnapshot) -> Vec<Transition> {
+        let readings = [
+            (MetricKind::Cpu, s.cpu.map(|m| (m.value.0 as f64, m.level)), "%"),
+            (MetricKind::Mem, s.mem.map(|m| (m.value.0 as f64, m.level)), "%"),
+            (MetricKind::Nw, s.nw.map(|m| (m.value.0 as f64, m.level)), "ms"),
+        ];
+        let mut out: Vec<Transition> = readings
+            .into_iter()
+            // 値が取れなかった回は前回のレベルのまま扱う
+            .filter_map(|(kind, reading, unit)| {
+                let (value, level) = reading?;
+                self.check(ts_ms, kind.key(), kind.label(), Some(value), unit, level)
+            })
+            .collect();
+        for p in &s.procs {
+            let cpu = p.cpu.map(|m| (m.value.0 as f64, m.level));
+            let rss = p.rss_gb.map(|m| (m.value as f64, m.level));
+            if let Some((value, level)) = cpu {
+                out.extend(self.check(ts_ms, &p.cpu_key(), &format!("{} CPU", p.name), Some(value), "%", level));
+            }
+            if let Some((value, level)) = rss {
+                out.extend(self.check(ts_ms, &p.rss_key(), &format!("{} RSS", p.name), Some(value), "GB", level));
+            }
+        }
+        for d in &s.derived {
+            out.extend(self.check(ts_ms, &d.name, &d.label, d.value, &d.unit, d.level));
+        }
+        out
+    }
+
+    /// 起動直後の最初の値は Warning 以上のときだけ知らせる
+    pub fn check(
+        &mut self,
+        ts_ms: u64,
+        metric: &str,
+        label: &str,
+        value: Option<f64>,
+        unit: &str,
+        level: AlertLevel,
+    ) -> Option<Transition> {
+        let previous = self.last.insert(metric.to_string(), level);
+        let changed = match previous {
+            Some(prev) => prev != level,
+            None => level >= AlertLevel::Warning,
+        };
+        changed.then(|| Transition {
+            ts_ms,
+            metric: metric.to_string(),
+            label: label.to_string(),
+            value,
+            unit: unit.to_string(),
+            previous,
+            level,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::types::MetricsSnapshot;
+    use crate::ui::types::Policies;
+
+    fn classify(cpu: Option<f32>) -> ClassifiedSnapshot {
+        let snap = MetricsSnapshot { cpu_pct: cpu, ..MetricsSnapshot::default() };
+        ClassifiedSnapshot::new(snap, &Policies::default())
+    }
+
+    #[test]
+    fn reports_only_level_changes() {
+        let mut tracker = TransitionTracker::default();
+        assert!(tracker.observe(1, &classify(Some(10.0))).is_empty());
+        assert!(tracker.observe(2, &classify(Some(20.0))).is_empty());
+
+        let up = tracker.observe(3, &classify(Some(95.0)));
+        assert_eq!(up.len(), 1);
+        assert_eq!((up[0].previous, up[0].level), (Some(AlertLevel::Safe), AlertLevel::Critical));
+        assert_eq!((up[0].label.as_str(), up[0].value_text()), ("CPU", "95%".to_string()));
+
+        // 値が取れなかった回を挟んでも同じレベルなら出さない
+        assert!(tracker.observe(4, &classify(None)).is_empty());
+        assert!(tracker.observe(5, &classify(Some(96.0))).is_empty());
+        assert_eq!(tracker.observe(6, &classify(Some(10.0)))[0].previous, Some(AlertLevel::Critical));
+    }
+
+    #[test]
+    fn first_reading_is_reported_only_when_alerting() {
+        let mut tracker = TransitionTracker::default();
+        let first = tracker.observe(1, &classify(Some(80.0)));
+        assert_eq!((first[0].previous, first[0].level), (None, AlertLevel::Warning));
+    }
+}
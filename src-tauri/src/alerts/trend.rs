@@ -0,0 +1,179 @@
+// 履歴の傾きから出すアラート
+//
+// - 上昇速度: 窓の中の値に最小二乗で直線を当て、1分あたりの増え方を閾値で分類する
+// - 枯渇予測: その傾きのまま 100% に達するまでの分数。減っている・横ばいなら Safe
+// 窓の8割以上の履歴がたまるまでは判定しない（起動直後の数点で騒がないように）
+use crate::config::schema::{ExhaustionRule, RiseRule, TrendConfig};
+use crate::metrics::history::History;
+use crate::metrics::types::MetricsSnapshot;
+use crate::ui::types::{level_by_threshold, AlertLevel, DerivedAlert, Metric, Policy};
+
+const MIN_COVERAGE: f64 = 0.8;
+const MIN_POINTS: usize = 10;
+// これより先の枯渇は「当面なし」として扱う（1週間）
+const HORIZON_MINS: f64 = 7.0 * 24.0 * 60.0;
+
+/// 1分あたりの上昇量を分類する
+pub struct RisePolicy(pub RiseRule);
+
+impl Policy<f64> for RisePolicy {
+    fn level(&self, per_min: &f64) -> AlertLevel {
+        level_by_threshold(*per_min as f32, self.0.per_min)
+    }
+}
+
+/// 100% までの残り分数を分類する（短いほど深刻）
+pub struct ExhaustionPolicy(pub ExhaustionRule);
+
+impl Policy<f64> for ExhaustionPolicy {
+    fn level(&self, mins: &f64) -> AlertLevel {
+        let mins = *mins as f32;
+        if mins <= self.0.critical_mins {
+            AlertLevel::Critical
+        } else if mins <= self.0.warning_mins {
+            AlertLevel::Warning
+        } else if (mins as f64) < HORIZON_MINS {
+            AlertLevel::Normal
+        } else {
+            AlertLevel::Safe
+        }
+    }
+}
+
+type Extract = fn(&MetricsSnapshot) -> Option<f64>;
+
+fn mem(s: &MetricsSnapshot) -> Option<f64> {
+    s.mem_pressure_pct.map(f64::from)
+}
+
+fn disk(s: &MetricsSnapshot) -> Option<f64> {
+    s.disk_used_pct.map(f64::from)
+}
+
+pub fn evaluate(cfg: &TrendConfig, history: &History, ts_ms: u64) -> Vec<DerivedAlert> {
+    let mut out = Vec::new();
+
+    let rule = cfg.mem_rise;
+    if rule.enabled {
+        if let Some(per_min) = window(history, ts_ms, rule.window_secs, mem).and_then(|p| slope_per_min(&p)) {
+            out.push(alert("mem_rise", "Mem↗", "%/min", Metric::classify(per_min, &RisePolicy(rule))));
+        }
+    }
+
+    let exhaustion: [(&str, &str, ExhaustionRule, Extract); 2] = [
+        ("mem_exhaustion", "Mem full in", cfg.mem_exhaustion, mem),
+        ("disk_exhaustion", "Disk full in", cfg.disk_exhaustion, disk),
+    ];
+    for (name, label, rule, value) in exhaustion {
+        if !rule.enabled {
+            continue;
+        }
+        if let Some(mins) = window(history, ts_ms, rule.window_secs, value).and_then(|p| minutes_to_full(&p)) {
+            out.push(alert(name, label, "min", Metric::classify(mins, &ExhaustionPolicy(rule))));
+        }
+    }
+    out
+}
+
+fn alert(name: &str, label: &str, unit: &str, m: Metric<f64>) -> DerivedAlert {
+    DerivedAlert {
+        name: name.to_string(),
+        label: label.to_string(),
+        value: Some(m.value),
+        unit: unit.to_string(),
+        level: m.level,
+    }
+}
+
+/// 窓を十分に覆う履歴があるときだけ返す
+fn window(history: &History, ts_ms: u64, secs: u64, value: Extract) -> Option<Vec<(u64, f64)>> {
+    let points = history.series(ts_ms, secs, value);
+    let (first, _) = *points.first()?;
+    let covered = (ts_ms - first) as f64 >= secs as f64 * 1000.0 * MIN_COVERAGE;
+    (covered && points.len() >= MIN_POINTS).then_some(points)
+}
+
+/// 最小二乗法で当てた直線の傾き（1分あたり）
+pub fn slope_per_min(points: &[(u64, f64)]) -> Option<f64> {
+    let (t0, _) = *points.first()?;
+    let n = points.len() as f64;
+    let xs: Vec<f64> = points.iter().map(|(t, _)| (t - t0) as f64 / 60_000.0).collect();
+    let mean_x = xs.iter().sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, v)| v).sum::<f64>() / n;
+
+    let (mut sxx, mut sxy) = (0.0, 0.0);
+    for (x, (_, y)) in xs.iter().zip(points) {
+        sxx += (x - mean_x) * (x - mean_x);
+        sxy += (x - mean_x) * (y - mean_y);
+    }
+    (sxx > 0.0).then(|| sxy / sxx)
+}
+
+/// 今の傾きのまま 100% に達するまでの分数（増えていなければ HORIZON_MINS）
+pub fn minutes_to_full(points: &[(u64, f64)]) -> Option<f64> {
+    let slope = slope_per_min(points)?;
+    let (_, current) = *points.last()?;
+    if slope <= 0.0 {
+        return Some(HORIZON_MINS);
+    }
+    Some(((100.0 - current).max(0.0) / slope).min(HORIZON_MINS))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::history::Sample;
+
+    // 1秒ごと・secs 秒分のメモリ圧力の履歴
+    fn history(secs: u64, mem_at: impl Fn(u64) -> f32) -> History {
+        let mut h = History::new(3600);
+        for i in 0..=secs {
+            let snapshot = MetricsSnapshot { mem_pressure_pct: Some(mem_at(i)), ..MetricsSnapshot::default() };
+            h.push(Sample { ts_ms: i * 1000, snapshot });
+        }
+        h
+    }
+
+    fn find<'a>(alerts: &'a [DerivedAlert], name: &str) -> &'a DerivedAlert {
+        alerts.iter().find(|a| a.name == name).unwrap()
+    }
+
+    #[test]
+    fn fits_slope_per_minute() {
+        let points: Vec<(u64, f64)> = (0..=10).map(|i| (i * 60_000, 40.0 + 1.5 * i as f64)).collect();
+        assert!((slope_per_min(&points).unwrap() - 1.5).abs() < 1e-9);
+        assert!((minutes_to_full(&points).unwrap() - 30.0).abs() < 1e-9);
+        assert_eq!(minutes_to_full(&[(0, 50.0), (60_000, 40.0)]), Some(HORIZON_MINS));
+        assert_eq!(slope_per_min(&[(0, 50.0)]), None);
+    }
+
+    #[test]
+    fn slow_leak_raises_rise_and_exhaustion_alerts() {
+        // 10分で 60% → 75%（1.5%/分）。閾値だけなら Warning 手前
+        let h = history(600, |i| 60.0 + 1.5 * i as f32 / 60.0);
+        let alerts = evaluate(&TrendConfig::default(), &h, 600_000);
+
+        let rise = find(&alerts, "mem_rise");
+        assert_eq!(rise.level, AlertLevel::Warning);
+        assert!((rise.value.unwrap() - 1.5).abs() < 1e-3);
+
+        // 残り 25% を 1.5%/分 → 約17分
+        let full = find(&alerts, "mem_exhaustion");
+        assert_eq!(full.level, AlertLevel::Warning);
+        assert!((full.value.unwrap() - 16.7).abs() < 0.1);
+
+        // ディスクの値が無ければ出さない
+        assert!(alerts.iter().all(|a| a.name != "disk_exhaustion"));
+    }
+
+    #[test]
+    fn waits_until_the_window_is_covered() {
+        let h = history(120, |i| 50.0 + i as f32);
+        assert!(evaluate(&TrendConfig::default(), &h, 120_000).is_empty());
+
+        let flat = history(600, |_| 70.0);
+        let alerts = evaluate(&TrendConfig::default(), &flat, 600_000);
+        assert_eq!(find(&alerts, "mem_rise").level, AlertLevel::Safe);
+        assert_eq!(find(&alerts, "mem_exhaustion").level, AlertLevel::Safe);
+    }
+}
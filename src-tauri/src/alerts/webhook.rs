@@ -0,0 +1,260 @@
+// レベルの遷移を Webhook（汎用 JSON / Slack 互換）に POST する
+//
+// - 通知先ごとにタスクを1つ持ち、届いた順に送る
+// - 同じメトリクスは min_interval_secs に1回まで。その間の変化は最後の1件にまとめる
+// - 失敗（接続できない・429・5xx）は間隔を倍にしながら max_retries 回までやり直す
+// - 送信は curl に任せる（https の Slack にもそのまま送れる）
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+use crate::alerts::transitions::Transition;
+use crate::config::schema::{WebhookConfig, WebhookFormat};
+use crate::export::http::post_json;
+use crate::ui::types::{AlertLevel, IconTheme};
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// 設定が変わったら通知先ごとのタスクを作り直す（古いタスクは手元の分を送り終えて止まる）
+#[derive(Default)]
+pub struct Dispatcher {
+    configs: Vec<WebhookConfig>,
+    workers: Vec<mpsc::UnboundedSender<Transition>>,
+}
+
+impl Dispatcher {
+    pub fn configure(&mut self, hooks: &[WebhookConfig]) {
+        if self.configs == hooks {
+            return;
+        }
+        self.configs = hooks.to_vec();
+        self.workers = hooks
+            .iter()
+            .map(|cfg| {
+                let (tx, rx) = mpsc::unbounded_channel();
+                tauri::async_runtime::spawn(run_worker(cfg.clone(), crate::export::host_name(), rx));
+                tx
+            })
+            .collect();
+    }
+
+    pub fn send(&self, transition: &Transition) {
+        for (cfg, tx) in self.configs.iter().zip(&self.workers) {
+            if is_relevant(cfg.min_level, transition) {
+                let _ = tx.send(transition.clone());
+            }
+        }
+    }
+}
+
+/// min_level 以上に上がったとき、または min_level 以上から下がったとき
+pub fn is_relevant(min_level: AlertLevel, t: &Transition) -> bool {
+    t.level >= min_level || t.previous.is_some_and(|prev| prev >= min_level)
+}
+
+pub async fn run_worker(cfg: WebhookConfig, host: String, mut rx: mpsc::UnboundedReceiver<Transition>) {
+    let min_interval = Duration::from_secs(cfg.min_interval_secs);
+    let mut last_sent: HashMap<String, Instant> = HashMap::new();
+    let mut pending: HashMap<String, Transition> = HashMap::new();
+
+    loop {
+        let due = pending
+            .keys()
+            .filter_map(|metric| last_sent.get(metric).map(|t| *t + min_interval))
+            .min();
+        tokio::select! {
+            received = rx.recv() => {
+                let Some(t) = received else { break; };
+                // まとめるときは「最後に送った時点のレベル」を previous に残す
+                pending
+                    .entry(t.metric.clone())
+                    .and_modify(|p| *p = Transition { previous: p.previous, ..t.clone() })
+                    .or_insert(t);
+            }
+            _ = tokio::time::sleep_until(due.unwrap_or_else(Instant::now)), if due.is_some() => {}
+        }
+
+        let now = Instant::now();
+        let ready: Vec<String> = pending
+            .keys()
+            .filter(|metric| last_sent.get(*metric).is_none_or(|t| now >= *t + min_interval))
+            .cloned()
+            .collect();
+        for metric in ready {
+            let Some(t) = pending.remove(&metric) else { continue; };
+            // まとめた結果、元のレベルに戻っていれば送らない
+            if t.previous == Some(t.level) {
+                continue;
+            }
+            last_sent.insert(metric, now);
+            deliver(&cfg, &host, &t).await;
+        }
+    }
+
+    // 作り直されて止まるときも、まとめて待っていた分は間隔を守って送り切る
+    let mut rest: Vec<(Instant, Transition)> = pending
+        .into_values()
+        .filter(|t| t.previous != Some(t.level))
+        .map(|t| {
+            let due = last_sent.get(&t.metric).map_or_else(Instant::now, |sent| *sent + min_interval);
+            (due, t)
+        })
+        .collect();
+    rest.sort_by_key(|(due, _)| *due);
+    for (due, t) in rest {
+        tokio::time::sleep_until(due).await;
+        deliver(&cfg, &host, &t).await;
+    }
+}
+
+async fn deliver(cfg: &WebhookConfig, host: &str, t: &Transition) {
+    let body = payload(cfg, host, t);
+    let mut backoff = INITIAL_BACKOFF;
+    for attempt in 0..=cfg.max_retries {
+        match post_json(&cfg.url, &body).await {
+            Ok(status) if (200..300).contains(&status) => return,
+            // 送り直しても通らないもの
+            Ok(status) if status < 500 && status != 429 => {
+                eprintln!("webhook {}: rejected with HTTP {}", cfg.url, status);
+                return;
+            }
+            Ok(status) => eprintln!("webhook {}: HTTP {} (attempt {})", cfg.url, status, attempt + 1),
+            Err(e) => eprintln!("webhook {}: {} (attempt {})", cfg.url, e, attempt + 1),
+        }
+        if attempt < cfg.max_retries {
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    }
+    eprintln!("webhook {}: giving up on {} {:?}", cfg.url, t.metric, t.level);
+}
+
+pub fn render_message(template: &str, host: &str, t: &Transition) -> String {
+    let previous = t.previous.map(|l| format!("{:?}", l)).unwrap_or_else(|| "none".to_string());
+    template
+        .replace("{metric}", &t.label)
+        .replace("{level}", &format!("{:?}", t.level))
+        .replace("{previous}", &previous)
+        .replace("{value}", &t.value_text())
+        .replace("{icon}", t.level.icon(&IconTheme::Emoji))
+        .replace("{host}", host)
+}
+
+pub fn payload(cfg: &WebhookConfig, host: &str, t: &Transition) -> String {
+    let text = render_message(&cfg.template, host, t);
+    let value = match cfg.format {
+        WebhookFormat::Slack => serde_json::json!({ "text": text }),
+        WebhookFormat::Json => serde_json::json!({
+            "text": text,
+            "host": host,
+            "metric": t.metric,
+            "level": t.level,
+            "previous": t.previous,
+            "value": t.value,
+            "unit": t.unit,
+            "ts_ms": t.ts_ms,
+        }),
+    };
+    value.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+
+    /// 受け取った本文を返しつつ、statuses の順に応答する Webhook の代役
+    async fn stand_in(statuses: Vec<u16>) -> (String, mpsc::UnboundedReceiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            for status in statuses {
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut reader = BufReader::new(stream);
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).await.unwrap();
+                    if let Some(v) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                        length = v.trim().parse().unwrap();
+                    }
+                    if line == "\r\n" {
+                        break;
+                    }
+                }
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).await.unwrap();
+                tx.send(String::from_utf8(body).unwrap()).unwrap();
+
+                let response = format!("HTTP/1.1 {} X\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status);
+                reader.into_inner().write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        (url, rx)
+    }
+
+    #[test]
+    fn renders_template_and_slack_payload() {
+        let cfg = WebhookConfig { format: WebhookFormat::Slack, ..WebhookConfig::default() };
+        let t = Transition::sample(Some(AlertLevel::Warning), AlertLevel::Critical);
+        assert_eq!(payload(&cfg, "build-01", &t), r#"{"text":"🔴 build-01: CPU is Critical (95%, was Warning)"}"#);
+
+        assert!(is_relevant(AlertLevel::Warning, &t));
+        assert!(is_relevant(AlertLevel::Warning, &Transition::sample(Some(AlertLevel::Critical), AlertLevel::Safe)));
+        assert!(!is_relevant(AlertLevel::Warning, &Transition::sample(Some(AlertLevel::Safe), AlertLevel::Normal)));
+    }
+
+    #[tokio::test]
+    async fn retries_failed_deliveries() {
+        let (url, mut received) = stand_in(vec![503, 200]).await;
+        let cfg = WebhookConfig { url, max_retries: 2, ..WebhookConfig::default() };
+
+        deliver(&cfg, "build-01", &Transition::sample(Some(AlertLevel::Safe), AlertLevel::Critical)).await;
+
+        let first: serde_json::Value = serde_json::from_str(&received.recv().await.unwrap()).unwrap();
+        let second = received.recv().await.unwrap();
+        assert_eq!(first["level"], "Critical");
+        assert_eq!(first["metric"], "cpu");
+        assert_eq!(first.to_string(), second);
+    }
+
+    #[tokio::test]
+    async fn coalesces_transitions_within_the_interval() {
+        let (url, mut received) = stand_in(vec![200, 200]).await;
+        let cfg = WebhookConfig { url, min_interval_secs: 1, ..WebhookConfig::default() };
+        let (tx, rx) = mpsc::unbounded_channel();
+        tokio::spawn(run_worker(cfg, "build-01".to_string(), rx));
+
+        tx.send(Transition::sample(Some(AlertLevel::Safe), AlertLevel::Warning)).unwrap();
+        let first: serde_json::Value = serde_json::from_str(&received.recv().await.unwrap()).unwrap();
+        assert_eq!(first["level"], "Warning");
+
+        // 間隔内の2件は最後の1件にまとめられ、previous は送った時点のレベルになる
+        tx.send(Transition::sample(Some(AlertLevel::Warning), AlertLevel::Critical)).unwrap();
+        tx.send(Transition::sample(Some(AlertLevel::Critical), AlertLevel::Safe)).unwrap();
+        let second: serde_json::Value = serde_json::from_str(&received.recv().await.unwrap()).unwrap();
+        assert_eq!((second["previous"].as_str(), second["level"].as_str()), (Some("Warning"), Some("Safe")));
+    }
+
+    #[tokio::test]
+    async fn flushes_pending_transitions_when_replaced() {
+        let (url, mut received) = stand_in(vec![200, 200]).await;
+        let cfg = WebhookConfig { url, min_interval_secs: 1, ..WebhookConfig::default() };
+        let (tx, rx) = mpsc::unbounded_channel();
+        let worker = tokio::spawn(run_worker(cfg, "build-01".to_string(), rx));
+
+        tx.send(Transition::sample(Some(AlertLevel::Safe), AlertLevel::Warning)).unwrap();
+        received.recv().await.unwrap();
+
+        // 間隔内に届いた分を持ったまま送り口が閉じられる
+        tx.send(Transition::sample(Some(AlertLevel::Warning), AlertLevel::Critical)).unwrap();
+        drop(tx);
+        let second: serde_json::Value = serde_json::from_str(&received.recv().await.unwrap()).unwrap();
+        assert_eq!(second["level"], "Critical");
+        worker.await.unwrap();
+    }
+}
@@ -0,0 +1,4 @@
+// トレイ無しで動かすヘッドレス版（使い方は vital-cli --help）
+fn main() {
+    std::process::exit(vaital_monitor_lib::cli::main())
+}
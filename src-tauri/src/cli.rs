@@ -0,0 +1,220 @@
+// トレイの無い環境（SSH 先など）向けのヘッドレス実行（bin/vital-cli.rs から呼ぶ）
+//
+// Tauri のアプリは起動せず、metrics のコレクタだけを動かして format_list の結果を標準出力に出す
+// 終了コードは一番悪いレベルから決める（Nagios 方式）:
+//   0 = Safe/Normal, 1 = Warning, 2 = Critical, 3 = 表示対象の値が取れなかった
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+use crate::alerts::evaluate::Evaluator;
+use crate::config::store;
+use crate::ipc::MetricsUpdate;
+use crate::metrics::history::{History, Sample, HISTORY_CAPACITY};
+use crate::metrics::service::SamplingControl;
+use crate::metrics::types::{MetricsSnapshot, SharedMetrics};
+use crate::ui::types::{AlertLevel, ClassifiedSnapshot, MetricKind, TrayConfig};
+use crate::updater::template::CompiledTemplates;
+use crate::updater::tray_updater::format_list;
+
+// tauri.conf.json の identifier（アプリと同じ設定ファイルを読む）
+const IDENTIFIER: &str = "com.fuji.vital-monitor";
+
+const USAGE: &str = "\
+usage: vital-cli [--once] [--json] [--interval SECS] [--config PATH]
+
+  --once           print one reading and exit with a status derived from the worst level
+  --json           print each reading as one JSON line instead of the tray text
+  --interval SECS  seconds between readings (default: 2)
+  --config PATH    settings file (default: the app's tray_config.json)
+
+exit status: 0 = ok, 1 = warning, 2 = critical, 3 = unknown";
+
+#[derive(Debug, PartialEq)]
+pub struct Args {
+    pub once: bool,
+    pub json: bool,
+    pub interval_secs: u64,
+    pub config: Option<PathBuf>,
+}
+
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Args, String> {
+    let mut parsed = Args { once: false, json: false, interval_secs: 2, config: None };
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--once" => parsed.once = true,
+            "--json" => parsed.json = true,
+            "--interval" => {
+                let value = args.next().ok_or("--interval needs a value")?;
+                parsed.interval_secs = value
+                    .parse()
+                    .ok()
+                    .filter(|secs| *secs > 0)
+                    .ok_or_else(|| format!("invalid interval: {}", value))?;
+            }
+            "--config" => parsed.config = Some(args.next().ok_or("--config needs a value")?.into()),
+            other => return Err(format!("unknown argument: {}", other)),
+        }
+    }
+    Ok(parsed)
+}
+
+/// 戻り値はそのままプロセスの終了コードにする
+pub fn main() -> i32 {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|a| a == "-h" || a == "--help") {
+        println!("{}", USAGE);
+        return 0;
+    }
+    let args = match parse_args(args) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("vital-cli: {}\n\n{}", e, USAGE);
+            return 64;
+        }
+    };
+    let cfg = match load_config(args.config.clone()) {
+        Ok(cfg) => cfg,
+        Err(e) => {
+            eprintln!("vital-cli: {}", e);
+            return 3;
+        }
+    };
+
+    tauri::async_runtime::block_on(run(args, cfg))
+}
+
+fn load_config(path: Option<PathBuf>) -> Result<TrayConfig, String> {
+    let explicit = path.is_some();
+    let Some(path) = path.or_else(|| dirs::config_dir().map(|d| d.join(IDENTIFIER).join(store::CONFIG_FILE))) else {
+        return Ok(TrayConfig::default());
+    };
+    match store::load(&path) {
+        Ok(Some(cfg)) => Ok(cfg),
+        Ok(None) if !explicit => Ok(TrayConfig::default()),
+        Ok(None) => Err(format!("{}: not found", path.display())),
+        Err(e) => Err(format!("{}: {}", path.display(), e)),
+    }
+}
+
+async fn run(args: Args, cfg: TrayConfig) -> i32 {
+    let metrics: SharedMetrics = Arc::new(RwLock::new(MetricsSnapshot::default()));
+    let sampling = SamplingControl::new(&cfg);
+    crate::metrics::service::spawn_metric_tasks(Arc::clone(&metrics), sampling.subscribe(), sampling.subscribe_processes());
+
+    let templates = CompiledTemplates::compile(&cfg.active_templates()).unwrap_or_default();
+    // 上昇速度などの判定用にアプリと同じだけ履歴を持つ
+    let mut history = History::new(HISTORY_CAPACITY);
+    let mut evaluator = Evaluator::default();
+
+    // 最初の1回は全部そろうまで待つ（NW は timeout まで掛かることがある）
+    let deadline = Instant::now() + Duration::from_secs(cfg.sampling.nw_timeout_secs + 2);
+    while !all_shown_present(&cfg, &*metrics.read().await) && Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    let mut tick = tokio::time::interval(Duration::from_secs(args.interval_secs));
+    loop {
+        tick.tick().await;
+
+        let sample = Sample::now(metrics.read().await.clone());
+        history.push(sample.clone());
+        let classified = evaluator.evaluate(&cfg, &history, &sample);
+        let update = MetricsUpdate::new(sample, classified);
+        if args.json {
+            println!("{}", serde_json::to_string(&update).unwrap_or_default());
+        } else {
+            println!("{}", format_list(&cfg, &update.classified, &templates));
+        }
+
+        if args.once {
+            return exit_code(&cfg, &update.classified);
+        }
+    }
+}
+
+fn all_shown_present(cfg: &TrayConfig, snapshot: &MetricsSnapshot) -> bool {
+    MetricKind::ALL.into_iter().filter(|k| cfg.is_shown(*k)).all(|kind| match kind {
+        MetricKind::Cpu => snapshot.cpu_pct.is_some(),
+        MetricKind::Mem => snapshot.mem_pressure_pct.is_some(),
+        MetricKind::Nw => snapshot.nw_ms.is_some(),
+    })
+}
+
+pub fn exit_code(cfg: &TrayConfig, s: &ClassifiedSnapshot) -> i32 {
+    let shown: Vec<MetricKind> = MetricKind::ALL.into_iter().filter(|k| cfg.is_shown(*k)).collect();
+    // 派生アラートと見張っているプロセスも表示に出るので一番悪いレベルに含める
+    let derived = s.derived.iter().map(|d| d.level);
+    let procs = s.procs.iter().filter_map(|p| p.level());
+    let worst = s.worst_of(shown.iter().copied()).map(|(_, level)| level).into_iter().chain(derived).chain(procs).max();
+    let missing = shown.iter().any(|kind| s.level_of(*kind).is_none());
+
+    match worst {
+        Some(AlertLevel::Critical) => 2,
+        Some(AlertLevel::Warning) => 1,
+        _ if missing => 3,
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::schema::ProcessWatch;
+    use crate::metrics::types::ProcessSample;
+    use crate::ui::types::{DerivedAlert, Policies, ProcessMetric};
+
+    fn args(list: &[&str]) -> Result<Args, String> {
+        parse_args(list.iter().map(|s| s.to_string()))
+    }
+
+    #[test]
+    fn parses_flags() {
+        let parsed = args(&["--once", "--json", "--interval", "5"]).unwrap();
+        assert_eq!(parsed, Args { once: true, json: true, interval_secs: 5, config: None });
+        assert!(args(&["--interval", "0"]).is_err());
+        assert!(args(&["--verbose"]).is_err());
+    }
+
+    #[test]
+    fn exit_code_follows_worst_shown_level() {
+        let cfg = TrayConfig::default();
+        let classify = |cpu: f32, nw: Option<f64>| {
+            let snap = MetricsSnapshot { cpu_pct: Some(cpu), mem_pressure_pct: Some(10.0), nw_ms: nw, ..MetricsSnapshot::default() };
+            ClassifiedSnapshot::new(snap, &Policies::default())
+        };
+
+        assert_eq!(exit_code(&cfg, &classify(10.0, Some(5.0))), 0);
+        assert_eq!(exit_code(&cfg, &classify(80.0, Some(5.0))), 1);
+        assert_eq!(exit_code(&cfg, &classify(95.0, None)), 2);
+        assert_eq!(exit_code(&cfg, &classify(10.0, None)), 3);
+
+        let cpu_only = TrayConfig { show_nw: false, ..TrayConfig::default() };
+        assert_eq!(exit_code(&cpu_only, &classify(10.0, None)), 0);
+
+        // 派生アラートは値の取れない項目より優先する
+        let mut rising = classify(10.0, None);
+        rising.derived.push(DerivedAlert {
+            name: "mem_rise".to_string(),
+            label: "Mem↗".to_string(),
+            value: Some(2.5),
+            unit: "%/min".to_string(),
+            level: AlertLevel::Warning,
+        });
+        assert_eq!(exit_code(&cfg, &rising), 1);
+        rising.derived[0].level = AlertLevel::Critical;
+        assert_eq!(exit_code(&cpu_only, &rising), 2);
+
+        // 見張っているプロセスも同じ。動いていないプロセスは値が取れない扱いにしない
+        let watch = ProcessWatch { name: "node".to_string(), ..ProcessWatch::default() };
+        let mut busy = classify(10.0, Some(5.0));
+        let sample = ProcessSample { name: "node".to_string(), count: 1, cpu_pct: Some(400.0), rss_bytes: Some(1 << 30) };
+        busy.procs.push(ProcessMetric::classify(&sample, &watch));
+        assert_eq!(exit_code(&cfg, &busy), 2);
+        let mut stopped = classify(10.0, Some(5.0));
+        stopped.procs.push(ProcessMetric::classify(&ProcessSample { name: "node".to_string(), ..ProcessSample::default() }, &watch));
+        assert_eq!(exit_code(&cfg, &stopped), 0);
+    }
+}
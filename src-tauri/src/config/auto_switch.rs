@@ -0,0 +1,143 @@
+// 今いるネットワークや電源に合わせてプロファイルを自動で切り替える
+//
+// profiles.json の例:
+//   "rules": [
+//     { "profile": "On battery", "on_battery": true },
+//     { "profile": "Office", "ssid": "Office-5G" },
+//     { "profile": "Home VPN", "gateway": "10.8.0.1" }
+//   ]
+//
+// 環境が変わって当てはまるルールが変わったときだけ切り替える
+// （手動で選んだプロファイルを毎回上書きしないため）
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::Manager;
+
+use crate::config::profiles::{self, ProfileState};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(15);
+
+/// 書かれた条件をすべて満たしたときに当てはまる（条件が1つも無いルールは使わない）
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProfileRule {
+    pub profile: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gateway: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssid: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_battery: Option<bool>,
+}
+
+/// 取得できなかった項目は None（その条件を持つルールは当てはまらない）
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Environment {
+    pub gateway: Option<String>,
+    pub ssid: Option<String>,
+    pub on_battery: Option<bool>,
+}
+
+impl ProfileRule {
+    pub fn matches(&self, env: &Environment) -> bool {
+        if self.gateway.is_none() && self.ssid.is_none() && self.on_battery.is_none() {
+            return false;
+        }
+        let same = |want: &Option<String>, got: &Option<String>| match want {
+            None => true,
+            Some(want) => got.as_deref().is_some_and(|got| got.eq_ignore_ascii_case(want)),
+        };
+        same(&self.gateway, &env.gateway)
+            && same(&self.ssid, &env.ssid)
+            && self.on_battery.is_none_or(|want| env.on_battery == Some(want))
+    }
+}
+
+pub fn pick_profile<'a>(rules: &'a [ProfileRule], env: &Environment) -> Option<&'a str> {
+    rules.iter().find(|r| r.matches(env)).map(|r| r.profile.as_str())
+}
+
+async fn read_environment() -> Environment {
+    let (gateway, ssid, on_battery) = tokio::join!(
+        crate::mac_metrics::default_gateway(),
+        crate::mac_metrics::wifi_ssid(),
+        crate::mac_metrics::on_battery_power(),
+    );
+    Environment {
+        gateway: gateway.ok().flatten(),
+        ssid: ssid.ok().flatten(),
+        on_battery: on_battery.ok(),
+    }
+}
+
+pub fn spawn_profile_switcher(app: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut tick = tokio::time::interval(POLL_INTERVAL);
+        let mut last_pick: Option<String> = None;
+        loop {
+            tick.tick().await;
+
+            let (enabled, rules) = {
+                let profiles = app.state::<ProfileState>();
+                profiles.reload_rules();
+                let file = profiles.file.lock().unwrap();
+                (file.auto_switch, file.rules.clone())
+            };
+            if !enabled || rules.is_empty() {
+                last_pick = None;
+                continue;
+            }
+
+            let env = read_environment().await;
+            let pick = pick_profile(&rules, &env).map(str::to_string);
+            if pick == last_pick {
+                continue;
+            }
+            last_pick = pick.clone();
+
+            // どれにも当てはまらなければ今のまま
+            let Some(name) = pick else { continue; };
+            if app.state::<ProfileState>().active().as_deref() == Some(name.as_str()) {
+                continue;
+            }
+            match profiles::switch(&app, &name) {
+                Ok(()) => crate::ui::notify::notify("Vital Monitor", &format!("Switched to profile \"{}\"", name)),
+                Err(e) => eprintln!("auto switch to {:?}: {}", name, e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(profile: &str, gateway: Option<&str>, ssid: Option<&str>, on_battery: Option<bool>) -> ProfileRule {
+        ProfileRule {
+            profile: profile.to_string(),
+            gateway: gateway.map(str::to_string),
+            ssid: ssid.map(str::to_string),
+            on_battery,
+        }
+    }
+
+    #[test]
+    fn first_matching_rule_wins() {
+        let rules = vec![
+            rule("On battery", None, None, Some(true)),
+            rule("Office", None, Some("Office-5G"), None),
+            rule("Home VPN", Some("10.8.0.1"), None, None),
+            rule("Never", None, None, None),
+        ];
+
+        let office = Environment { ssid: Some("office-5g".into()), on_battery: Some(false), ..Default::default() };
+        assert_eq!(pick_profile(&rules, &office), Some("Office"));
+
+        let unplugged = Environment { on_battery: Some(true), ..office };
+        assert_eq!(pick_profile(&rules, &unplugged), Some("On battery"));
+
+        let vpn = Environment { gateway: Some("10.8.0.1".into()), ..Default::default() };
+        assert_eq!(pick_profile(&rules, &vpn), Some("Home VPN"));
+
+        assert_eq!(pick_profile(&rules, &Environment::default()), None);
+    }
+}
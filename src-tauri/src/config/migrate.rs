@@ -0,0 +1,50 @@
+// 古い形式の設定を現在のスキーマに合わせる
+//
+// v1: version フィールドが無いフラットな形式
+// v2: version / thresholds / sampling を追加
+use serde_json::Value;
+
+use crate::config::schema::ConfigError;
+
+pub const CURRENT_VERSION: u32 = 2;
+
+pub fn migrate(mut value: Value) -> Result<Value, ConfigError> {
+    let mut version = match value.get("version") {
+        None => 1,
+        Some(v) => match v.as_u64() {
+            Some(0) => return Err(invalid_version(v)),
+            Some(n) => u32::try_from(n).map_err(|_| invalid_version(v))?,
+            None => return Err(invalid_version(v)),
+        },
+    };
+
+    if version > CURRENT_VERSION {
+        return Err(ConfigError::UnsupportedVersion(version));
+    }
+
+    while version < CURRENT_VERSION {
+        value = match version {
+            1 => v1_to_v2(value),
+            // 移行の手順が無い版は読めない
+            _ => return Err(ConfigError::UnsupportedVersion(version)),
+        };
+        version += 1;
+    }
+
+    if let Some(obj) = value.as_object_mut() {
+        obj.insert("version".to_string(), Value::from(CURRENT_VERSION));
+    }
+    Ok(value)
+}
+
+fn invalid_version(v: &Value) -> ConfigError {
+    ConfigError::Invalid(vec![format!("version: {} is not a valid config version", v)])
+}
+
+// 新しいセクションはすべて既定値で埋まるので version を付けるだけ
+fn v1_to_v2(mut value: Value) -> Value {
+    if let Some(obj) = value.as_object_mut() {
+        obj.insert("version".to_string(), Value::from(2));
+    }
+    value
+}
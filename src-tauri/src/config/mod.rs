@@ -0,0 +1,7 @@
+pub mod auto_switch;
+pub mod schema;
+pub mod migrate;
+pub mod profiles;
+pub mod store;
+pub mod watch;
+pub mod writer;
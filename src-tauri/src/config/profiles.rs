@@ -0,0 +1,243 @@
+// 設定プロファイル（"Office" / "Home VPN" / "On battery" など）の保存と切り替え
+//
+// - プロファイルは tray_config.json と同じ形式で `profiles/<名前>.json` に置く
+// - どのプロファイルを読み込んだかと自動切り替えのルールは `profiles.json` に置く
+// - 切り替え後にメニューで変えた内容は tray_config.json にだけ保存され、プロファイルは変わらない
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::Manager;
+
+use crate::config::auto_switch::ProfileRule;
+use crate::config::schema::ConfigError;
+use crate::config::store;
+use crate::config::writer::write_atomic;
+use crate::ui::types::{TrayConfig, TrayUiState};
+
+pub const PROFILES_DIR: &str = "profiles";
+const STATE_FILE: &str = "profiles.json";
+const MAX_NAME_LEN: usize = 64;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProfilesFile {
+    /// 最後に読み込んだプロファイル
+    pub active: Option<String>,
+    pub auto_switch: bool,
+    /// 上から順に見て最初に当てはまったものを使う
+    pub rules: Vec<ProfileRule>,
+}
+
+impl Default for ProfilesFile {
+    fn default() -> Self {
+        Self { active: None, auto_switch: true, rules: Vec::new() }
+    }
+}
+
+pub struct ProfileState {
+    pub file: Mutex<ProfilesFile>,
+    dir: Option<PathBuf>,
+    state_path: Option<PathBuf>,
+}
+
+impl ProfileState {
+    /// 読めなければ何も選ばれていない状態で始める
+    pub fn load(app: &tauri::AppHandle) -> Self {
+        let dir = profiles_dir(app);
+        let state_path = app.path().app_config_dir().ok().map(|d| d.join(STATE_FILE));
+        let file = state_path.as_deref().and_then(read_state).unwrap_or_default();
+        Self { file: Mutex::new(file), dir, state_path }
+    }
+
+    /// 手で書き換えられたルールを拾う（読めなければ今のまま）
+    pub fn reload_rules(&self) {
+        let Some(read) = self.state_path.as_deref().and_then(read_state) else { return; };
+        let mut file = self.file.lock().unwrap();
+        file.auto_switch = read.auto_switch;
+        file.rules = read.rules;
+    }
+
+    pub fn active(&self) -> Option<String> {
+        self.file.lock().unwrap().active.clone()
+    }
+
+    /// 保存済みのプロファイル名（名前順）
+    pub fn names(&self) -> Vec<String> {
+        self.dir.as_deref().map(list).unwrap_or_default()
+    }
+
+    fn dir(&self) -> Result<&Path, ConfigError> {
+        self.dir
+            .as_deref()
+            .ok_or_else(|| ConfigError::Invalid(vec!["profiles directory is not available".to_string()]))
+    }
+}
+
+fn read_state(path: &Path) -> Option<ProfilesFile> {
+    let text = std::fs::read_to_string(path).ok()?;
+    match serde_json::from_str(&text) {
+        Ok(file) => Some(file),
+        Err(e) => {
+            eprintln!("{}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+pub fn profiles_dir(app: &tauri::AppHandle) -> Option<PathBuf> {
+    app.path().app_config_dir().ok().map(|dir| dir.join(PROFILES_DIR))
+}
+
+/// ファイル名にそのまま使うので、区切り文字や隠しファイルになる名前は受け付けない
+pub fn validate_name(name: &str) -> Result<&str, ConfigError> {
+    let name = name.trim();
+    let invalid = |msg: &str| Err(ConfigError::Invalid(vec![format!("profile name {:?}: {}", name, msg)]));
+
+    if name.is_empty() {
+        return invalid("must not be empty");
+    }
+    if name.chars().count() > MAX_NAME_LEN {
+        return invalid("is too long");
+    }
+    if name.starts_with('.') || name.contains(['/', '\\', ':']) || name.chars().any(char::is_control) {
+        return invalid("contains characters that cannot be used in a file name");
+    }
+    Ok(name)
+}
+
+pub fn list(dir: &Path) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(dir) else { return Vec::new(); };
+    let mut names: Vec<String> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|p| p.file_stem().and_then(|s| s.to_str()).map(str::to_string))
+        .filter(|name| validate_name(name).is_ok())
+        .collect();
+    names.sort();
+    names
+}
+
+pub fn read_profile(dir: &Path, name: &str) -> Result<TrayConfig, ConfigError> {
+    let name = validate_name(name)?;
+    store::load(&dir.join(format!("{}.json", name)))?
+        .ok_or_else(|| ConfigError::Invalid(vec![format!("profile {:?} not found", name)]))
+}
+
+pub fn write_profile(dir: &Path, name: &str, cfg: &TrayConfig) -> Result<(), ConfigError> {
+    let name = validate_name(name)?;
+    write_atomic(&dir.join(format!("{}.json", name)), cfg)?;
+    Ok(())
+}
+
+/// 外部のファイルを検証してから取り込む。名前はファイル名から付ける
+pub fn import_file(dir: &Path, src: &Path) -> Result<String, ConfigError> {
+    let cfg = store::parse(&std::fs::read_to_string(src)?)?;
+    let name = src.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+    let name = validate_name(name)?.to_string();
+    // 移行済みの形で保存し直す
+    write_profile(dir, &name, &cfg)?;
+    Ok(name)
+}
+
+/// 今の設定を名前付きプロファイルとして書き出す
+pub fn export_current(app: &tauri::AppHandle, name: &str) -> Result<String, ConfigError> {
+    let profiles = app.state::<ProfileState>();
+    let name = validate_name(name)?.to_string();
+    let cfg = app.state::<TrayUiState>().config.lock().unwrap().clone();
+    write_profile(profiles.dir()?, &name, &cfg)?;
+
+    set_active(app, Some(name.clone()));
+    Ok(name)
+}
+
+/// 今の設定を選ばれた場所に書き出す（別の Mac に持っていくときなど）
+/// 取り込むときはファイル名がプロファイル名になる
+pub fn export_to_file(app: &tauri::AppHandle, dest: &Path) -> Result<(), ConfigError> {
+    let cfg = app.state::<TrayUiState>().config.lock().unwrap().clone();
+    write_file(dest, &cfg)
+}
+
+pub fn write_file(dest: &Path, cfg: &TrayConfig) -> Result<(), ConfigError> {
+    write_atomic(dest, cfg)?;
+    Ok(())
+}
+
+pub fn import(app: &tauri::AppHandle, src: &Path) -> Result<String, ConfigError> {
+    let profiles = app.state::<ProfileState>();
+    let name = import_file(profiles.dir()?, src)?;
+    crate::ui::tray::refresh_profiles_menu(app);
+    Ok(name)
+}
+
+/// プロファイルを読み込んで今の設定として適用する
+pub fn switch(app: &tauri::AppHandle, name: &str) -> Result<(), ConfigError> {
+    let profiles = app.state::<ProfileState>();
+    let cfg = read_profile(profiles.dir()?, name)?;
+    store::apply(app, cfg);
+
+    set_active(app, Some(validate_name(name)?.to_string()));
+    Ok(())
+}
+
+fn set_active(app: &tauri::AppHandle, active: Option<String>) {
+    update_state(app, |file| file.active = active);
+}
+
+/// profiles.json を書き換えてメニューにも反映する
+pub fn update_state(app: &tauri::AppHandle, f: impl FnOnce(&mut ProfilesFile)) {
+    let profiles = app.state::<ProfileState>();
+    let file = {
+        let mut file = profiles.file.lock().unwrap();
+        f(&mut file);
+        file.clone()
+    };
+
+    if let Some(path) = &profiles.state_path {
+        if let Err(e) = write_atomic(path, &file) {
+            eprintln!("failed to save {}: {}", path.display(), e);
+        }
+    }
+    crate::ui::tray::refresh_profiles_menu(app);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_names_that_escape_the_profiles_dir() {
+        assert_eq!(validate_name("  Home VPN ").unwrap(), "Home VPN");
+        assert!(validate_name("").is_err());
+        assert!(validate_name("../tray_config").is_err());
+        assert!(validate_name(".hidden").is_err());
+    }
+
+    #[test]
+    fn imports_and_lists_profiles() {
+        let dir = std::env::temp_dir().join(format!("vital-monitor-profiles-{}", std::process::id()));
+        let src_dir = dir.join("src");
+        std::fs::create_dir_all(&src_dir).unwrap();
+
+        let src = src_dir.join("On battery.json");
+        std::fs::write(&src, r#"{"show_cpu":false,"sampling":{"cpu_secs":5}}"#).unwrap();
+
+        let profiles = dir.join(PROFILES_DIR);
+        assert_eq!(import_file(&profiles, &src).unwrap(), "On battery");
+        write_profile(&profiles, "Office", &TrayConfig::default()).unwrap();
+
+        assert_eq!(list(&profiles), vec!["Office".to_string(), "On battery".to_string()]);
+        let cfg = read_profile(&profiles, "On battery").unwrap();
+        assert!(!cfg.show_cpu);
+        assert_eq!(cfg.sampling.cpu_secs, 5);
+        assert!(read_profile(&profiles, "Missing").is_err());
+
+        // 書き出したファイルはそのまま取り込める
+        let exported = src_dir.join("Home VPN.json");
+        write_file(&exported, &TrayConfig { show_nw: false, ..TrayConfig::default() }).unwrap();
+        assert_eq!(import_file(&profiles, &exported).unwrap(), "Home VPN");
+        assert!(!read_profile(&profiles, "Home VPN").unwrap().show_nw);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
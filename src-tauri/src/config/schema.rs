@@ -0,0 +1,544 @@
+// 設定ファイル（tray_config.json）のスキーマと検証
+use std::collections::BTreeMap;
+use std::fmt;
+use serde::{Deserialize, Serialize};
+
+use crate::alerts::quiet::parse_time;
+use crate::alerts::rules::Expr;
+use crate::metrics::history::HISTORY_CAPACITY;
+use crate::ui::types::{AlertLevel, AlertThresholds, CpuPolicy, MemoryPolicy, MetricKind, NetworkPolicy, Policies, TrayConfig};
+use crate::updater::template::CompiledTemplates;
+
+/// メトリクスごとのアラート閾値
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThresholdConfig {
+    pub cpu: AlertThresholds,
+    pub mem: AlertThresholds,
+    pub nw: AlertThresholds,
+}
+
+impl Default for ThresholdConfig {
+    fn default() -> Self {
+        Self {
+            cpu: CpuPolicy::default().thresholds,
+            mem: MemoryPolicy::default().thresholds,
+            nw: NetworkPolicy::default().thresholds,
+        }
+    }
+}
+
+impl ThresholdConfig {
+    pub fn policies(&self) -> Policies {
+        Policies {
+            cpu: CpuPolicy { thresholds: self.cpu },
+            mem: MemoryPolicy { thresholds: self.mem },
+            nw: NetworkPolicy { thresholds: self.nw },
+        }
+    }
+}
+
+/// 名前か正規表現で選んだプロセスの CPU% と RSS（metrics::process を参照）
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProcessWatch {
+    // トレイや通知に出す名前
+    pub name: String,
+    // プロセス名に対する正規表現。空なら name と同じ名前のプロセス
+    pub pattern: String,
+    // 一致したプロセスの合計。CPU は1コア使い切って 100%
+    pub cpu: AlertThresholds,
+    pub rss_gb: AlertThresholds,
+    // false ならトレイには Warning 以上のときだけ出す
+    pub show: bool,
+}
+
+impl Default for ProcessWatch {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            pattern: String::new(),
+            cpu: AlertThresholds::new(50.0, 100.0, 200.0),
+            rss_gb: AlertThresholds::new(1.0, 2.0, 4.0),
+            show: true,
+        }
+    }
+}
+
+/// 取得間隔（秒）と NW の計測先
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SamplingConfig {
+    pub cpu_secs: u64,
+    pub mem_secs: u64,
+    pub nw_secs: u64,
+    pub nw_timeout_secs: u64,
+    // 先頭が代表値（nw_ms）になる
+    pub nw_targets: Vec<String>,
+}
+
+impl Default for SamplingConfig {
+    fn default() -> Self {
+        Self {
+            cpu_secs: 1,
+            mem_secs: 1,
+            nw_secs: 3,
+            nw_timeout_secs: 2,
+            nw_targets: vec!["1.1.1.1".to_string()],
+        }
+    }
+}
+
+/// 履歴の傾きから出すアラート（alerts::trend を参照）
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrendConfig {
+    pub mem_rise: RiseRule,
+    pub mem_exhaustion: ExhaustionRule,
+    pub disk_exhaustion: ExhaustionRule,
+}
+
+impl Default for TrendConfig {
+    fn default() -> Self {
+        Self {
+            mem_rise: RiseRule::default(),
+            mem_exhaustion: ExhaustionRule::default(),
+            // ディスクはゆっくり増えるので長めの窓で見る
+            disk_exhaustion: ExhaustionRule { window_secs: 1800, ..ExhaustionRule::default() },
+        }
+    }
+}
+
+/// window_secs の間の上昇速度（1分あたり）を閾値で分類する
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RiseRule {
+    pub enabled: bool,
+    pub window_secs: u64,
+    pub per_min: AlertThresholds,
+}
+
+impl Default for RiseRule {
+    fn default() -> Self {
+        Self { enabled: true, window_secs: 600, per_min: AlertThresholds::new(0.5, 1.0, 2.0) }
+    }
+}
+
+/// window_secs の傾きのまま 100% に達するまでの分数で分類する（短いほど深刻）
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExhaustionRule {
+    pub enabled: bool,
+    pub window_secs: u64,
+    pub warning_mins: f32,
+    pub critical_mins: f32,
+}
+
+impl Default for ExhaustionRule {
+    fn default() -> Self {
+        Self { enabled: true, window_secs: 600, warning_mins: 60.0, critical_mins: 15.0 }
+    }
+}
+
+/// 時間帯ごとに覚えた「いつもの値」からのずれで判定する（alerts::anomaly を参照）
+/// 有効にしたメトリクスは、学習が済んだ時間帯だけ閾値と継続時間の代わりにこちらのレベルになる
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AnomalyConfig {
+    pub enabled: bool,
+    pub metrics: Vec<MetricKind>,
+    // EWMA の重み（1秒ごとに1件覚える）
+    pub alpha: f64,
+    // 時間帯ごとに、この件数を覚えるまでは判定に使わない
+    pub warm_up_samples: u64,
+    // z スコアの閾値
+    pub z: AlertThresholds,
+}
+
+impl Default for AnomalyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            metrics: MetricKind::ALL.to_vec(),
+            alpha: 0.001,
+            warm_up_samples: 3600,
+            z: AlertThresholds::new(2.0, 3.0, 4.0),
+        }
+    }
+}
+
+/// 外部に数値を出す口（どれも既定では無効）
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExportConfig {
+    pub prometheus: PrometheusConfig,
+    pub local_api: LocalApiConfig,
+    pub statsd: StatsdConfig,
+    pub otlp: OtlpConfig,
+    // StatsD / OTLP に付けるタグ（host はホスト名で自動的に付く）
+    pub host_tags: BTreeMap<String, String>,
+}
+
+/// 127.0.0.1 の `/metrics` で Prometheus 形式を返す
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PrometheusConfig {
+    pub enabled: bool,
+    pub port: u16,
+}
+
+impl Default for PrometheusConfig {
+    fn default() -> Self {
+        Self { enabled: false, port: 9464 }
+    }
+}
+
+/// StatsD（UDP, DogStatsD 形式のタグ付き）に一定間隔で送る
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StatsdConfig {
+    pub enabled: bool,
+    pub address: String,
+    pub prefix: String,
+    pub flush_secs: u64,
+}
+
+impl Default for StatsdConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            address: "127.0.0.1:8125".to_string(),
+            prefix: "vital.".to_string(),
+            flush_secs: 10,
+        }
+    }
+}
+
+/// OpenTelemetry Collector の OTLP/HTTP（JSON）に一定間隔で送る
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OtlpConfig {
+    pub enabled: bool,
+    pub endpoint: String,
+    pub flush_secs: u64,
+}
+
+impl Default for OtlpConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: "http://127.0.0.1:4318/v1/metrics".to_string(),
+            flush_secs: 10,
+        }
+    }
+}
+
+/// スクリプト向けの JSON / NDJSON（export::local_api を参照）
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LocalApiConfig {
+    pub enabled: bool,
+    // 未指定ならアプリのデータフォルダの vital.sock
+    pub socket_path: Option<String>,
+    // 指定したときだけ 127.0.0.1 で HTTP も受ける
+    pub http_port: Option<u16>,
+}
+
+/// 自前のアラート条件と通知先
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AlertsConfig {
+    pub rules: Vec<RuleConfig>,
+    pub notifications: NotificationConfig,
+    pub webhooks: Vec<WebhookConfig>,
+    pub quiet: QuietConfig,
+}
+
+/// 通知と Webhook を止める時間帯（alerts::quiet を参照）。トレイのアイコンは変わる
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QuietConfig {
+    pub windows: Vec<QuietWindow>,
+    // 明けたときに、止めていた間の変化をまとめて送る
+    pub digest: bool,
+}
+
+impl Default for QuietConfig {
+    fn default() -> Self {
+        Self { windows: Vec::new(), digest: true }
+    }
+}
+
+/// ローカル時刻の "HH:MM"。start > end なら日付をまたぐ
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct QuietWindow {
+    pub start: String,
+    pub end: String,
+}
+
+/// 条件式が成り立つ間、name のアラートを level にする（alerts::rules を参照）
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RuleConfig {
+    pub name: String,
+    // 例: "cpu > 85 for 2m AND nw_p95 >= 200"
+    pub when: String,
+    pub level: AlertLevel,
+}
+
+impl Default for RuleConfig {
+    fn default() -> Self {
+        Self { name: String::new(), when: String::new(), level: AlertLevel::Warning }
+    }
+}
+
+/// macOS の通知センターに出す
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationConfig {
+    pub enabled: bool,
+    // このレベル以上に上がったときだけ出す（戻ったときは出さない）
+    pub min_level: AlertLevel,
+}
+
+impl Default for NotificationConfig {
+    fn default() -> Self {
+        Self { enabled: true, min_level: AlertLevel::Warning }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WebhookFormat {
+    /// 遷移の内容をそのまま JSON で POST する
+    #[default]
+    Json,
+    /// Slack の Incoming Webhook 形式（{"text": ...}）
+    Slack,
+}
+
+/// レベルが変わったときに POST する（alerts::webhook を参照）
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebhookConfig {
+    pub url: String,
+    pub format: WebhookFormat,
+    // このレベル以上になったとき・ここから戻ったときに送る
+    pub min_level: AlertLevel,
+    // {metric} {level} {previous} {value} {icon} {host} を置き換える
+    pub template: String,
+    // 同じメトリクスについて送る最短間隔（その間の変化は最後の1件にまとめる）
+    pub min_interval_secs: u64,
+    pub max_retries: u32,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            format: WebhookFormat::default(),
+            min_level: AlertLevel::Warning,
+            template: "{icon} {host}: {metric} is {level} ({value}, was {previous})".to_string(),
+            min_interval_secs: 60,
+            max_retries: 3,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(std::io::Error),
+    Parse(serde_json::Error),
+    UnsupportedVersion(u32),
+    Invalid(Vec<String>),
+}
+
+impl From<std::io::Error> for ConfigError {
+    fn from(e: std::io::Error) -> Self { ConfigError::Io(e) }
+}
+impl From<serde_json::Error> for ConfigError {
+    fn from(e: serde_json::Error) -> Self { ConfigError::Parse(e) }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(e) => write!(f, "failed to read config: {}", e),
+            ConfigError::Parse(e) => write!(f, "failed to parse config: {}", e),
+            ConfigError::UnsupportedVersion(v) => {
+                write!(f, "config version {} is newer than this app supports", v)
+            }
+            ConfigError::Invalid(issues) => write!(f, "invalid config: {}", issues.join("; ")),
+        }
+    }
+}
+
+impl ConfigError {
+    /// 画面に出す用に1件ずつに分ける
+    pub fn messages(&self) -> Vec<String> {
+        match self {
+            ConfigError::Invalid(issues) => issues.clone(),
+            other => vec![other.to_string()],
+        }
+    }
+}
+
+impl TrayConfig {
+    pub fn policies(&self) -> Policies {
+        self.thresholds.policies()
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut issues = Vec::new();
+
+        let densities = [
+            ("templates", &self.templates),
+            ("compact_templates", &self.compact_templates),
+            ("ultra_compact_templates", &self.ultra_compact_templates),
+        ];
+        for (name, templates) in densities {
+            if let Err(e) = CompiledTemplates::compile(templates) {
+                issues.push(format!("{}: {}", name, e));
+            }
+        }
+        if self.rotation_secs == 0 {
+            issues.push("rotation_secs must be at least 1".to_string());
+        }
+        if self.rotation_order.is_empty() {
+            issues.push("rotation_order must not be empty".to_string());
+        }
+        if self.max_width == Some(0) {
+            issues.push("max_width must be at least 1".to_string());
+        }
+
+        for (name, t) in [("cpu", &self.thresholds.cpu), ("mem", &self.thresholds.mem), ("nw", &self.thresholds.nw)] {
+            if !t.is_ordered() {
+                issues.push(format!("thresholds.{}: expected normal <= warning <= critical", name));
+            }
+            if t.longest_for_secs() > HISTORY_CAPACITY as u64 {
+                issues.push(format!("thresholds.{}: *_for_secs must be at most {}", name, HISTORY_CAPACITY));
+            }
+        }
+
+        let t = &self.trends;
+        if !t.mem_rise.per_min.is_ordered() {
+            issues.push("trends.mem_rise.per_min: expected normal <= warning <= critical".to_string());
+        }
+        for (name, secs) in [("mem_rise", t.mem_rise.window_secs), ("mem_exhaustion", t.mem_exhaustion.window_secs), ("disk_exhaustion", t.disk_exhaustion.window_secs)] {
+            if !(60..=HISTORY_CAPACITY as u64).contains(&secs) {
+                issues.push(format!("trends.{}.window_secs must be between 60 and {}", name, HISTORY_CAPACITY));
+            }
+        }
+        for (name, rule) in [("mem_exhaustion", &t.mem_exhaustion), ("disk_exhaustion", &t.disk_exhaustion)] {
+            if rule.critical_mins > rule.warning_mins {
+                issues.push(format!("trends.{}: expected critical_mins <= warning_mins", name));
+            }
+        }
+
+        let a = &self.anomaly;
+        if !(a.alpha > 0.0 && a.alpha <= 1.0) {
+            issues.push("anomaly.alpha must be in (0, 1]".to_string());
+        }
+        if !a.z.is_ordered() {
+            issues.push("anomaly.z: expected normal <= warning <= critical".to_string());
+        }
+
+        let s = &self.sampling;
+        for (name, secs) in [("cpu_secs", s.cpu_secs), ("mem_secs", s.mem_secs), ("nw_secs", s.nw_secs), ("nw_timeout_secs", s.nw_timeout_secs)] {
+            if secs == 0 {
+                issues.push(format!("sampling.{} must be at least 1", name));
+            }
+        }
+        if s.nw_targets.is_empty() {
+            issues.push("sampling.nw_targets must not be empty".to_string());
+        }
+        if s.nw_targets.iter().any(|t| t.trim().is_empty()) {
+            issues.push("sampling.nw_targets must not contain empty hosts".to_string());
+        }
+        // ping の引数になるのでオプションに見えるものは通さない
+        for target in s.nw_targets.iter().filter(|t| t.starts_with('-') || t.contains(char::is_whitespace)) {
+            if !target.trim().is_empty() {
+                issues.push(format!("sampling.nw_targets: '{}' is not a host name or address", target));
+            }
+        }
+
+        if self.export.prometheus.enabled && self.export.prometheus.port == 0 {
+            issues.push("export.prometheus.port must not be 0".to_string());
+        }
+        if self.export.local_api.http_port == Some(0) {
+            issues.push("export.local_api.http_port must not be 0".to_string());
+        }
+        if self.export.local_api.socket_path.as_deref().is_some_and(|p| p.trim().is_empty()) {
+            issues.push("export.local_api.socket_path must not be empty".to_string());
+        }
+
+        let e = &self.export;
+        if e.statsd.enabled && e.statsd.address.trim().is_empty() {
+            issues.push("export.statsd.address must not be empty".to_string());
+        }
+        if e.otlp.enabled && !(e.otlp.endpoint.starts_with("http://") || e.otlp.endpoint.starts_with("https://")) {
+            issues.push("export.otlp.endpoint must start with http:// or https://".to_string());
+        }
+        for (name, secs) in [("statsd", e.statsd.flush_secs), ("otlp", e.otlp.flush_secs)] {
+            if secs == 0 {
+                issues.push(format!("export.{}.flush_secs must be at least 1", name));
+            }
+        }
+
+        for (i, w) in self.processes.iter().enumerate() {
+            let name = w.name.trim();
+            if name.is_empty() {
+                issues.push(format!("processes[{}].name must not be empty", i));
+            } else if self.processes[..i].iter().any(|p| p.name.trim() == name) {
+                issues.push(format!("processes[{}].name '{}' is used more than once", i, name));
+            }
+            if let Err(e) = regex::Regex::new(&w.pattern) {
+                issues.push(format!("processes[{}].pattern: {}", i, e));
+            }
+            for (field, t) in [("cpu", &w.cpu), ("rss_gb", &w.rss_gb)] {
+                if !t.is_ordered() {
+                    issues.push(format!("processes[{}].{}: expected normal <= warning <= critical", i, field));
+                }
+                if t.longest_for_secs() > HISTORY_CAPACITY as u64 {
+                    issues.push(format!("processes[{}].{}: *_for_secs must be at most {}", i, field, HISTORY_CAPACITY));
+                }
+            }
+        }
+
+        // 名前は遷移や Webhook でメトリクスと同じように使うので、かぶらないようにする
+        let reserved = ["cpu", "mem", "nw", "mem_rise", "mem_exhaustion", "disk_exhaustion"];
+        for (i, rule) in self.alerts.rules.iter().enumerate() {
+            let name = rule.name.trim();
+            if name.is_empty() {
+                issues.push(format!("alerts.rules[{}].name must not be empty", i));
+            } else if reserved.contains(&name) {
+                issues.push(format!("alerts.rules[{}].name '{}' is reserved", i, name));
+            } else if name.contains(':') {
+                // "proc:<name>:cpu" などのプロセスのキーとかぶらないように
+                issues.push(format!("alerts.rules[{}].name '{}' must not contain ':'", i, name));
+            } else if self.alerts.rules[..i].iter().any(|r| r.name.trim() == name) {
+                issues.push(format!("alerts.rules[{}].name '{}' is used more than once", i, name));
+            }
+            if let Err(e) = Expr::parse(&rule.when) {
+                issues.push(format!("alerts.rules[{}].when: {}", i, e));
+            }
+        }
+
+        for (i, w) in self.alerts.quiet.windows.iter().enumerate() {
+            match (parse_time(&w.start), parse_time(&w.end)) {
+                (Some(start), Some(end)) if start == end => {
+                    issues.push(format!("alerts.quiet.windows[{}]: start and end must differ", i));
+                }
+                (Some(_), Some(_)) => {}
+                _ => issues.push(format!("alerts.quiet.windows[{}]: expected \"HH:MM\" times", i)),
+            }
+        }
+
+        for (i, hook) in self.alerts.webhooks.iter().enumerate() {
+            if !(hook.url.starts_with("http://") || hook.url.starts_with("https://")) {
+                issues.push(format!("alerts.webhooks[{}].url must start with http:// or https://", i));
+            }
+        }
+
+        if issues.is_empty() { Ok(()) } else { Err(ConfigError::Invalid(issues)) }
+    }
+}
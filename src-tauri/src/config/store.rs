@@ -0,0 +1,159 @@
+// 設定ファイルの読み書きと、実行中のアプリへの反映
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::Manager;
+
+use crate::config::migrate;
+use crate::config::schema::ConfigError;
+use crate::config::writer::ConfigWriter;
+use crate::metrics::service::SamplingControl;
+use crate::ui::types::{TrayConfig, TrayUiState};
+
+pub const CONFIG_FILE: &str = "tray_config.json";
+
+/// 起動時の読み込みで見つかった問題（設定画面で表示する）
+pub struct ConfigIssues(pub Mutex<Vec<String>>);
+
+pub fn config_path(app: &tauri::AppHandle) -> Option<PathBuf> {
+    app.path().app_config_dir().ok().map(|dir| dir.join(CONFIG_FILE))
+}
+
+/// JSON → 移行 → 型 → 検証
+pub fn parse(text: &str) -> Result<TrayConfig, ConfigError> {
+    let value: serde_json::Value = serde_json::from_str(text)?;
+    let value = migrate::migrate(value)?;
+    let cfg: TrayConfig = serde_json::from_value(value)?;
+    cfg.validate()?;
+    Ok(cfg)
+}
+
+/// ファイルが無ければ Ok(None)
+pub fn load(path: &Path) -> Result<Option<TrayConfig>, ConfigError> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => parse(&text).map(Some),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// 起動時の読み込み。失敗しても既定値で起動し、問題点を返す
+/// 壊れたファイルは次の保存で上書きされる前に `.invalid` として退避しておく
+pub fn load_initial(app: &tauri::AppHandle) -> (TrayConfig, Vec<String>) {
+    let Some(path) = config_path(app) else {
+        return (TrayConfig::default(), Vec::new());
+    };
+
+    match load(&path) {
+        Ok(cfg) => (cfg.unwrap_or_default(), Vec::new()),
+        Err(e) => {
+            eprintln!("{}: {}", path.display(), e);
+            let _ = std::fs::copy(&path, path.with_extension("json.invalid"));
+            (TrayConfig::default(), e.messages())
+        }
+    }
+}
+
+/// 保存は ConfigWriter に任せる（連続した変更はまとめて書かれる）
+pub fn save(app: &tauri::AppHandle, cfg: TrayConfig) {
+    if let Some(writer) = app.try_state::<ConfigWriter>() {
+        writer.save(cfg);
+    }
+}
+
+/// 検証済みの設定を丸ごと差し替えて保存する
+pub fn apply(app: &tauri::AppHandle, cfg: TrayConfig) {
+    apply_runtime(app, cfg.clone());
+    save(app, cfg);
+}
+
+/// 実行中の状態にだけ反映する（メニューの状態・取得間隔も合わせる）
+/// ファイルから読み直したときはこちらを使い、書き戻さない
+pub fn apply_runtime(app: &tauri::AppHandle, cfg: TrayConfig) {
+    if let Some(sampling) = app.try_state::<SamplingControl>() {
+        sampling.update(&cfg);
+    }
+
+    let ui_state = app.state::<TrayUiState>();
+    *ui_state.config.lock().unwrap() = cfg;
+    ui_state.sync_menu_checks();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::migrate::CURRENT_VERSION;
+    use crate::ui::types::{DisplayMode, TitleTemplates};
+
+    #[test]
+    fn migrates_flat_v1_config() {
+        let v1 = r#"{"show_cpu":true,"show_mem":false,"show_nw":true,"mode":"Rotation","is_alert":false}"#;
+        let cfg = parse(v1).unwrap();
+        assert_eq!(cfg.version, CURRENT_VERSION);
+        assert!(!cfg.show_mem);
+        assert_eq!(cfg.mode, DisplayMode::Rotation);
+        assert_eq!(cfg.sampling.nw_targets, vec!["1.1.1.1".to_string()]);
+    }
+
+    #[test]
+    fn unknown_and_missing_fields_keep_the_rest() {
+        let cfg = parse(r#"{"version":2,"show_cpu":false,"some_future_field":1}"#).unwrap();
+        assert!(!cfg.show_cpu);
+        assert!(cfg.show_nw);
+    }
+
+    #[test]
+    fn partial_metric_template_fills_missing() {
+        let cfg = parse(r#"{"version":2,"templates":{"cpu":{"text":"C{cpu}"}}}"#).unwrap();
+        assert_eq!(cfg.templates.cpu.text, "C{cpu}");
+        assert_eq!(cfg.templates.cpu.missing, "--");
+        assert_eq!(cfg.templates.mem, TitleTemplates::default().mem);
+    }
+
+    #[test]
+    fn reports_validation_errors() {
+        let text = r#"{"version":2,"rotation_secs":0,"sampling":{"nw_targets":[]}}"#;
+        let issues = parse(text).unwrap_err().messages();
+        assert_eq!(issues.len(), 2);
+    }
+
+    #[test]
+    fn rejects_probe_targets_that_look_like_options() {
+        let text = r#"{"version":2,"sampling":{"nw_targets":["1.1.1.1","-f","8.8.8.8 -c 100"]}}"#;
+        let issues = parse(text).unwrap_err().messages();
+        assert_eq!(issues.len(), 2);
+        assert!(issues[0].contains("'-f'"));
+    }
+
+    #[test]
+    fn rejects_rule_names_that_clash_with_metric_keys() {
+        let text = r#"{"version":2,"alerts":{"rules":[{"name":"mem","when":"mem > 90"},{"name":"proc:node:cpu","when":"cpu > 90"}]}}"#;
+        let issues = parse(text).unwrap_err().messages();
+        assert_eq!(issues.len(), 2);
+        assert!(issues[1].contains("':'"));
+    }
+
+    #[test]
+    fn rejects_newer_versions() {
+        assert!(matches!(parse(r#"{"version":99}"#), Err(ConfigError::UnsupportedVersion(99))));
+    }
+
+    #[test]
+    fn rejects_version_zero() {
+        assert!(matches!(parse(r#"{"version":0}"#), Err(ConfigError::Invalid(_))));
+    }
+
+    #[test]
+    fn rejects_versions_beyond_u32() {
+        // u32 に切り詰めて 0 や小さい版として読まないこと
+        let text = format!(r#"{{"version":{}}}"#, u64::from(u32::MAX) + 1);
+        assert!(matches!(parse(&text), Err(ConfigError::Invalid(_))));
+        let text = format!(r#"{{"version":{}}}"#, u32::MAX);
+        assert!(matches!(parse(&text), Err(ConfigError::UnsupportedVersion(u32::MAX))));
+    }
+
+    #[test]
+    fn rejects_non_numeric_versions() {
+        assert!(matches!(parse(r#"{"version":"2"}"#), Err(ConfigError::Invalid(_))));
+        assert!(matches!(parse(r#"{"version":-1}"#), Err(ConfigError::Invalid(_))));
+    }
+}
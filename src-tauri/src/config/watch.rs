@@ -0,0 +1,140 @@
+// tray_config.json を外部から書き換えられたら読み直す
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+use tauri::Manager;
+
+use crate::config::schema::ConfigError;
+use crate::config::store::{self, ConfigIssues};
+use crate::config::writer::ConfigWriter;
+use crate::ui::types::TrayConfig;
+
+pub fn spawn_config_watcher(app: tauri::AppHandle) {
+    let poll_interval = 1;
+
+    tauri::async_runtime::spawn(async move {
+        let Some(path) = store::config_path(&app) else { return; };
+        let mut tick = tokio::time::interval(Duration::from_secs(poll_interval));
+
+        // 起動時に読んだ内容を基準にする
+        let mut detector = ChangeDetector::new(modified_at(&path));
+        loop {
+            tick.tick().await;
+            if detector.observe(modified_at(&path)) {
+                reload(&app, &path);
+            }
+        }
+    });
+}
+
+/// 更新時刻の変化を見て、読み直すべきときだけ true を返す
+struct ChangeDetector {
+    handled: Option<SystemTime>,
+    pending: Option<SystemTime>,
+}
+
+impl ChangeDetector {
+    fn new(handled: Option<SystemTime>) -> Self {
+        Self { handled, pending: None }
+    }
+
+    fn observe(&mut self, current: Option<SystemTime>) -> bool {
+        if current == self.handled {
+            self.pending = None;
+            return false;
+        }
+        // エディタの書き込み途中を拾わないよう、更新時刻が1周期落ち着くのを待つ
+        if self.pending != current {
+            self.pending = current;
+            return false;
+        }
+        self.handled = current;
+        self.pending = None;
+        true
+    }
+}
+
+fn modified_at(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// 外から書き換えられた設定だけを返す
+/// アプリ自身が書いた中身なら読み直さない（今の設定と比べると、遅れて見えた古い保存で新しい変更を戻してしまう）
+/// 削除されたときも今の設定のまま
+fn external_config(path: &Path, is_own_write: impl Fn(&str) -> bool) -> Result<Option<TrayConfig>, ConfigError> {
+    match std::fs::read_to_string(path) {
+        Ok(text) if is_own_write(&text) => Ok(None),
+        Ok(text) => store::parse(&text).map(Some),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn reload(app: &tauri::AppHandle, path: &Path) {
+    let issues = app.state::<ConfigIssues>();
+
+    let writer = app.try_state::<ConfigWriter>();
+    match external_config(path, |text| writer.as_ref().is_some_and(|w| w.is_own_write(text))) {
+        Ok(changed) => {
+            issues.0.lock().unwrap().clear();
+            if let Some(cfg) = changed {
+                store::apply_runtime(app, cfg);
+            }
+        }
+        // 直前の正しい設定を使い続ける
+        Err(e) => {
+            eprintln!("{}: {}", path.display(), e);
+            crate::ui::notify::notify("Vital Monitor: config rejected", &e.to_string());
+            *issues.0.lock().unwrap() = e.messages();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::writer::{write_atomic, OwnWrites};
+
+    fn at(secs: u64) -> Option<SystemTime> {
+        Some(SystemTime::UNIX_EPOCH + Duration::from_secs(secs))
+    }
+
+    #[test]
+    fn waits_for_the_modified_time_to_settle() {
+        let mut d = ChangeDetector::new(at(1));
+        assert!(!d.observe(at(1)));
+        // 書き込み途中: 変わり続けている間は読まない
+        assert!(!d.observe(at(2)));
+        assert!(!d.observe(at(3)));
+        assert!(d.observe(at(3)));
+        // 一度読んだら同じ時刻では読み直さない
+        assert!(!d.observe(at(3)));
+    }
+
+    #[test]
+    fn ignores_the_apps_own_writes() {
+        let dir = std::env::temp_dir().join(format!("vital-watch-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(store::CONFIG_FILE);
+
+        let own = OwnWrites::default();
+        let is_own = |text: &str| own.contains(text);
+
+        // B を書いたあとすぐ C を書いた: watcher が遅れて B を見ても C を戻さない
+        let b = TrayConfig { show_cpu: false, ..TrayConfig::default() };
+        let c = TrayConfig { show_mem: false, ..b.clone() };
+        own.remember(&b);
+        write_atomic(&path, &b).unwrap();
+        own.remember(&c);
+        assert!(external_config(&path, is_own).unwrap().is_none());
+        write_atomic(&path, &c).unwrap();
+        assert!(external_config(&path, is_own).unwrap().is_none());
+
+        // 外から書き換えられたものは読み直す
+        let edited = TrayConfig { show_cpu: true, ..c.clone() };
+        std::fs::write(&path, serde_json::to_string(&edited).unwrap()).unwrap();
+        assert_eq!(external_config(&path, is_own).unwrap(), Some(edited));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(external_config(&path, is_own).unwrap().is_none());
+    }
+}
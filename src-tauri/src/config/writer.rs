@@ -0,0 +1,172 @@
+// 設定ファイルへの書き込みを1つのタスクにまとめる
+//
+// - 連続した変更は debounce して最後の1件だけ書く
+// - 一時ファイルに書いて fsync → rename で置き換える（途中で落ちても壊れない）
+// - 置き換える前の正しい設定は `.bak` に残す
+use std::collections::VecDeque;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+use crate::config::store;
+use crate::ui::types::TrayConfig;
+
+const DEBOUNCE: Duration = Duration::from_millis(300);
+// watcher が遅れて古い保存を見ても自分の書き込みと分かるように、直近の数件を覚えておく
+const OWN_WRITES_KEPT: usize = 4;
+
+/// アプリ自身が書いた設定ファイルの中身
+#[derive(Default)]
+pub struct OwnWrites(Mutex<VecDeque<String>>);
+
+impl OwnWrites {
+    /// 書く前に覚える（書いた直後に watcher が読んでも取りこぼさない）
+    pub fn remember(&self, cfg: &TrayConfig) {
+        let Ok(json) = to_json(cfg) else { return; };
+        let mut written = self.0.lock().unwrap();
+        if written.contains(&json) {
+            return;
+        }
+        if written.len() == OWN_WRITES_KEPT {
+            written.pop_front();
+        }
+        written.push_back(json);
+    }
+
+    pub fn contains(&self, text: &str) -> bool {
+        self.0.lock().unwrap().iter().any(|json| json == text)
+    }
+}
+
+pub struct ConfigWriter {
+    tx: mpsc::UnboundedSender<TrayConfig>,
+    path: Option<PathBuf>,
+    // 終了時の同期書き込みとタスクの書き込みが重ならないように
+    write_lock: Arc<Mutex<()>>,
+    own_writes: Arc<OwnWrites>,
+}
+
+impl ConfigWriter {
+    pub fn spawn(app: &tauri::AppHandle) -> Self {
+        let (tx, mut rx) = mpsc::unbounded_channel::<TrayConfig>();
+        let path = store::config_path(app);
+        let write_lock = Arc::new(Mutex::new(()));
+        let own_writes = Arc::new(OwnWrites::default());
+
+        tauri::async_runtime::spawn({
+            let path = path.clone();
+            let write_lock = Arc::clone(&write_lock);
+            let own_writes = Arc::clone(&own_writes);
+            async move {
+                while let Some(mut latest) = rx.recv().await {
+                    // 静かになるまで待って最新だけを残す
+                    while let Ok(Some(next)) = tokio::time::timeout(DEBOUNCE, rx.recv()).await {
+                        latest = next;
+                    }
+
+                    let Some(path) = path.clone() else { continue; };
+                    let write_lock = Arc::clone(&write_lock);
+                    let own_writes = Arc::clone(&own_writes);
+                    let result = tokio::task::spawn_blocking(move || {
+                        let _guard = write_lock.lock().unwrap();
+                        own_writes.remember(&latest);
+                        write_atomic(&path, &latest)
+                    })
+                    .await;
+
+                    if let Ok(Err(e)) = result {
+                        report(&e);
+                    }
+                }
+            }
+        });
+
+        Self { tx, path, write_lock, own_writes }
+    }
+
+    pub fn save(&self, cfg: TrayConfig) {
+        let _ = self.tx.send(cfg);
+    }
+
+    /// 終了直前など、debounce を待たずにその場で書く
+    pub fn flush_blocking(&self, cfg: &TrayConfig) {
+        let Some(path) = &self.path else { return; };
+        let _guard = self.write_lock.lock().unwrap();
+        self.own_writes.remember(cfg);
+        if let Err(e) = write_atomic(path, cfg) {
+            eprintln!("failed to save {}: {}", path.display(), e);
+        }
+    }
+
+    /// ファイルの中身がアプリ自身の保存によるものか
+    pub fn is_own_write(&self, text: &str) -> bool {
+        self.own_writes.contains(text)
+    }
+}
+
+fn report(e: &std::io::Error) {
+    eprintln!("failed to save {}: {}", store::CONFIG_FILE, e);
+    crate::ui::notify::notify("Vital Monitor: failed to save settings", &e.to_string());
+}
+
+fn to_json(value: &impl serde::Serialize) -> std::io::Result<String> {
+    serde_json::to_string_pretty(value).map_err(std::io::Error::other)
+}
+
+/// 設定ファイル以外（プロファイルの書き出し・profiles.json）も同じ手順で書く
+pub fn write_atomic(path: &Path, value: &impl serde::Serialize) -> std::io::Result<()> {
+    let json = to_json(value)?;
+
+    // 中身が同じなら触らない（更新時刻が変わると watcher が反応するため）
+    if std::fs::read(path).is_ok_and(|cur| cur == json.as_bytes()) {
+        return Ok(());
+    }
+
+    let dir = path.parent().ok_or_else(|| std::io::Error::other("config path has no parent"))?;
+    std::fs::create_dir_all(dir)?;
+
+    let tmp = path.with_extension("json.tmp");
+    {
+        let mut file = std::fs::File::create(&tmp)?;
+        file.write_all(json.as_bytes())?;
+        file.sync_all()?;
+    }
+
+    // 今のファイルが正しい設定なら .bak として残す
+    if matches!(store::load(path), Ok(Some(_))) {
+        std::fs::copy(path, path.with_extension("json.bak"))?;
+    }
+
+    std::fs::rename(&tmp, path)?;
+    // rename 自体を永続化する
+    std::fs::File::open(dir)?.sync_all()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_previous_good_config_as_backup() {
+        let dir = std::env::temp_dir().join(format!("vital-monitor-writer-{}", std::process::id()));
+        let path = dir.join(store::CONFIG_FILE);
+
+        let first = TrayConfig::default();
+        write_atomic(&path, &first).unwrap();
+        assert!(!path.with_extension("json.bak").exists());
+
+        let second = TrayConfig { show_cpu: false, ..TrayConfig::default() };
+        write_atomic(&path, &second).unwrap();
+
+        let current = store::load(&path).unwrap().unwrap();
+        let backup = store::load(&path.with_extension("json.bak")).unwrap().unwrap();
+        assert_eq!(current, second);
+        assert_eq!(backup, first);
+        assert!(!path.with_extension("json.tmp").exists());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,192 @@
+// 記録した履歴を CSV に書き出す（チケットに添付する用）
+//
+// レベルは書き出す時点の設定で付け直す（閾値・継続時間・傾き・ルール。異常検知は除く）
+// Parquet には対応しない（表計算ソフトやチケットにそのまま貼れる CSV だけ）
+use std::io::Write;
+use std::path::Path;
+use tauri::Manager;
+
+use crate::alerts::evaluate::Evaluator;
+use crate::metrics::history::{History, HistoryRange, Sample, SharedHistory, HISTORY_CAPACITY};
+use crate::ui::types::{AlertLevel, ClassifiedSnapshot, Metric, TrayConfig, TrayUiState};
+
+/// 直近 minutes 分を path に書き出し、書いた行数を返す
+pub async fn export_history(app: &tauri::AppHandle, minutes: u64, path: &Path) -> Result<usize, String> {
+    if minutes == 0 {
+        return Err("minutes must be at least 1".to_string());
+    }
+    // 継続時間や傾きを付け直せるよう、範囲より前の履歴も読む
+    let samples = app.state::<SharedHistory>().read().await.range(HistoryRange::Between { from_ms: 0, to_ms: u64::MAX });
+    let from_ms = crate::metrics::history::now_ms().saturating_sub(minutes.saturating_mul(60_000));
+    let cfg = app.state::<TrayUiState>().config.lock().unwrap().clone();
+
+    let file = std::fs::File::create(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let mut out = std::io::BufWriter::new(file);
+    let written = write_csv(&mut out, &samples, from_ms, &cfg)
+        .and_then(|written| out.flush().map(|()| written))
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(written)
+}
+
+/// samples を古い順に評価し直し、from_ms 以降の行を書く
+/// 列は評価結果から作る（見張るプロセスや派生アラート・ルールは出てきた順に列を足す）
+pub fn write_csv<W: Write>(out: &mut W, samples: &[Sample], from_ms: u64, cfg: &TrayConfig) -> std::io::Result<usize> {
+    // 異常検知の基準値はトレイ側で学習中のものなので、書き出しでは使わない
+    let mut cfg = cfg.clone();
+    cfg.anomaly.enabled = false;
+
+    let mut replay = History::new(HISTORY_CAPACITY);
+    let mut evaluator = Evaluator::default();
+    let mut header: Vec<String> = Vec::new();
+    let mut rows: Vec<Vec<(String, String)>> = Vec::new();
+    for sample in samples {
+        replay.push(sample.clone());
+        let classified = evaluator.evaluate(&cfg, &replay, sample);
+        if sample.ts_ms < from_ms {
+            continue;
+        }
+        let row = columns(sample, &classified, &cfg);
+        for (column, _) in &row {
+            if !header.contains(column) {
+                header.push(column.clone());
+            }
+        }
+        rows.push(row);
+    }
+
+    write_row(out, &header)?;
+    for row in &rows {
+        let fields: Vec<String> = header
+            .iter()
+            .map(|column| row.iter().find(|(c, _)| c == column).map(|(_, v)| v.clone()).unwrap_or_default())
+            .collect();
+        write_row(out, &fields)?;
+    }
+    Ok(rows.len())
+}
+
+/// 1行分の (列名, 値)。計測先ごとの応答時間は `probe_ms[<計測先>]`、プロセスと派生アラートはレベルの名前（`proc:node:cpu` など）
+fn columns(sample: &Sample, classified: &ClassifiedSnapshot, cfg: &TrayConfig) -> Vec<(String, String)> {
+    let s = &sample.snapshot;
+    let nw_p95_level = classified.nw_p95.map(|v| Metric::classify(v, &cfg.policies().nw).level);
+    let mut row: Vec<(String, String)> = vec![
+        ("timestamp".into(), timestamp(sample.ts_ms)),
+        ("ts_ms".into(), sample.ts_ms.to_string()),
+        ("cpu_pct".into(), number(s.cpu_pct.map(f64::from))),
+        ("cpu_level".into(), level(classified.cpu.map(|m| m.level))),
+        ("mem_pressure_pct".into(), number(s.mem_pressure_pct.map(f64::from))),
+        ("mem_level".into(), level(classified.mem.map(|m| m.level))),
+        ("nw_ms".into(), number(s.nw_ms)),
+        ("nw_level".into(), level(classified.nw.map(|m| m.level))),
+        ("nw_p95_ms".into(), number(s.nw_p95_ms)),
+        ("nw_p95_level".into(), level(nw_p95_level)),
+        ("disk_used_pct".into(), number(s.disk_used_pct.map(f64::from))),
+    ];
+    for probe in &s.nw_probes {
+        row.push((format!("probe_ms[{}]", probe.target), number(probe.ms)));
+    }
+    for (p, sample) in classified.procs.iter().filter_map(|p| s.procs.iter().find(|s| s.name == p.name).map(|s| (p, s))) {
+        row.push((format!("{}_pct", p.cpu_key()), number(sample.cpu_pct.map(f64::from))));
+        row.push((format!("{}_level", p.cpu_key()), level(p.cpu.map(|m| m.level))));
+        row.push((format!("{}_bytes", p.rss_key()), sample.rss_bytes.map(|b| b.to_string()).unwrap_or_default()));
+        row.push((format!("{}_level", p.rss_key()), level(p.rss_gb.map(|m| m.level))));
+    }
+    for d in &classified.derived {
+        row.push((d.name.clone(), number(d.value)));
+        row.push((format!("{}_level", d.name), level(Some(d.level))));
+    }
+    row
+}
+
+// ローカル時刻（オフセット付き）
+fn timestamp(ts_ms: u64) -> String {
+    chrono::DateTime::from_timestamp_millis(ts_ms as i64)
+        .map(|t| t.with_timezone(&chrono::Local).to_rfc3339_opts(chrono::SecondsFormat::Millis, false))
+        .unwrap_or_default()
+}
+
+fn number(v: Option<f64>) -> String {
+    v.map(|v| format!("{:.2}", v)).unwrap_or_default()
+}
+
+fn level(l: Option<AlertLevel>) -> String {
+    l.map(|l| format!("{:?}", l)).unwrap_or_default()
+}
+
+fn write_row<W: Write>(out: &mut W, fields: &[String]) -> std::io::Result<()> {
+    let line: Vec<String> = fields.iter().map(|f| escape(f)).collect();
+    writeln!(out, "{}", line.join(","))
+}
+
+fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::types::{MetricsSnapshot, ProbeResult, ProcessSample};
+
+    fn sample(ts_ms: u64, cpu: f32, probes: &[(&str, Option<f64>)]) -> Sample {
+        let nw_probes: Vec<ProbeResult> = probes
+            .iter()
+            .map(|(target, ms)| ProbeResult { target: target.to_string(), ms: *ms })
+            .collect();
+        let snapshot = MetricsSnapshot {
+            cpu_pct: Some(cpu),
+            nw_ms: nw_probes.iter().find_map(|p| p.ms),
+            nw_probes,
+            ..MetricsSnapshot::default()
+        };
+        Sample { ts_ms, snapshot }
+    }
+
+    fn export(samples: &[Sample], from_ms: u64, cfg: &TrayConfig) -> (usize, String) {
+        let mut out = Vec::new();
+        let written = write_csv(&mut out, samples, from_ms, cfg).unwrap();
+        (written, String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn writes_levels_and_a_column_per_probe_target() {
+        let samples = vec![
+            sample(1_000, 10.0, &[("1.1.1.1", Some(12.0))]),
+            sample(2_000, 95.0, &[("1.1.1.1", None), ("gw,lan", Some(3.5))]),
+        ];
+        let (written, text) = export(&samples, 0, &TrayConfig::default());
+        let lines: Vec<Vec<&str>> = text.lines().map(|l| l.split(',').collect()).collect();
+
+        assert_eq!(written, 2);
+        assert_eq!(
+            text.lines().next().unwrap(),
+            "timestamp,ts_ms,cpu_pct,cpu_level,mem_pressure_pct,mem_level,nw_ms,nw_level,nw_p95_ms,nw_p95_level,disk_used_pct,probe_ms[1.1.1.1],\"probe_ms[gw,lan]\""
+        );
+        assert_eq!(&lines[1][1..], ["1000", "10.00", "Safe", "", "", "12.00", "Safe", "", "", "", "12.00", ""]);
+        assert_eq!(&lines[2][1..], ["2000", "95.00", "Critical", "", "", "3.50", "Safe", "", "", "", "", "3.50"]);
+    }
+
+    #[test]
+    fn adds_columns_for_processes_and_rules_and_skips_rows_before_the_range() {
+        let cfg = crate::config::store::parse(
+            r#"{"version":2,"processes":[{"name":"node"}],"alerts":{"rules":[{"name":"hot","when":"cpu > 90","level":"Critical"}]}}"#,
+        )
+        .unwrap();
+        let mut samples = vec![sample(1_000, 95.0, &[]), sample(2_000, 95.0, &[])];
+        samples[1].snapshot.procs = vec![ProcessSample { name: "node".into(), count: 1, cpu_pct: Some(50.0), rss_bytes: Some(1 << 30) }];
+
+        let (written, text) = export(&samples, 2_000, &cfg);
+        let header: Vec<&str> = text.lines().next().unwrap().split(',').collect();
+        let row: Vec<&str> = text.lines().nth(1).unwrap().split(',').collect();
+        let field = |column: &str| row[header.iter().position(|c| *c == column).unwrap()];
+
+        assert_eq!(written, 1);
+        assert_eq!(field("ts_ms"), "2000");
+        assert_eq!(field("proc:node:cpu_pct"), "50.00");
+        assert_eq!(field("proc:node:rss_bytes"), "1073741824");
+        assert_eq!(field("hot_level"), "Critical");
+    }
+}
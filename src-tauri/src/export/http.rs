@@ -0,0 +1,127 @@
+// localhost 向けの最小限の HTTP（GET だけ・keep-alive なし）と、外への POST（curl）
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::process::Command;
+
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+const MAX_HEADER_BYTES: usize = 8 * 1024;
+const REQUEST_TIMEOUT_SECS: u64 = 10;
+const ACCEPT_RETRY_MIN: Duration = Duration::from_millis(10);
+const ACCEPT_RETRY_MAX: Duration = Duration::from_secs(1);
+
+/// accept が失敗し続けたとき（ファイル記述子が尽きたときなど）に空回りしないよう待つ
+/// 失敗が続くたびに待ち時間を倍にし、成功したら reset する
+#[derive(Default)]
+pub struct AcceptBackoff {
+    delay: Option<Duration>,
+}
+
+impl AcceptBackoff {
+    pub fn reset(&mut self) {
+        self.delay = None;
+    }
+
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = self.delay.map_or(ACCEPT_RETRY_MIN, |d| (d * 2).min(ACCEPT_RETRY_MAX));
+        self.delay = Some(delay);
+        delay
+    }
+
+    pub async fn wait(&mut self, label: &str, e: &std::io::Error) {
+        let delay = self.next_delay();
+        eprintln!("{}: accept failed: {} (retrying in {:?})", label, e, delay);
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// リクエスト行から (メソッド, パス) を取り出し、ヘッダは読み捨てる
+pub async fn read_request(stream: &mut TcpStream) -> std::io::Result<(String, String)> {
+    let mut reader = BufReader::new(stream);
+    let read = async {
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line).await?;
+
+        let mut total = request_line.len();
+        loop {
+            let mut line = String::new();
+            let n = reader.read_line(&mut line).await?;
+            total += n;
+            if n == 0 || line == "\r\n" || line == "\n" || total > MAX_HEADER_BYTES {
+                break;
+            }
+        }
+        Ok::<_, std::io::Error>(request_line)
+    };
+    let request_line = tokio::time::timeout(READ_TIMEOUT, read)
+        .await
+        .map_err(|_| std::io::Error::new(std::io::ErrorKind::TimedOut, "request timeout"))??;
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    // クエリは使わないので落とす
+    let path = parts.next().unwrap_or_default().split('?').next().unwrap_or_default().to_string();
+    Ok((method, path))
+}
+
+pub async fn write_response<W: AsyncWrite + Unpin>(
+    stream: &mut W,
+    status: &str,
+    content_type: &str,
+    body: &str,
+) -> std::io::Result<()> {
+    let head = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len(),
+    );
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(body.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+pub async fn not_found<W: AsyncWrite + Unpin>(stream: &mut W) -> std::io::Result<()> {
+    write_response(stream, "404 Not Found", "text/plain; charset=utf-8", "not found\n").await
+}
+
+/// HTTP ステータスを返す。つながらなかったときは Err
+pub async fn post_json(url: &str, body: &str) -> Result<u16, String> {
+    let mut child = Command::new("curl")
+        .args(["-sS", "-o", "/dev/null", "-w", "%{http_code}", "-X", "POST"])
+        .args(["--max-time", &REQUEST_TIMEOUT_SECS.to_string()])
+        .args(["-H", "Content-Type: application/json", "--data-binary", "@-"])
+        .arg(url)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("failed to run curl: {}", e))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(body.as_bytes()).await.map_err(|e| e.to_string())?;
+    }
+    let output = child.wait_with_output().await.map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse()
+        .map_err(|_| "unexpected curl output".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accept_backoff_doubles_up_to_the_cap() {
+        let mut backoff = AcceptBackoff::default();
+        let delays: Vec<u128> = (0..9).map(|_| backoff.next_delay().as_millis()).collect();
+        assert_eq!(delays, [10, 20, 40, 80, 160, 320, 640, 1000, 1000]);
+        backoff.reset();
+        assert_eq!(backoff.next_delay(), ACCEPT_RETRY_MIN);
+    }
+}
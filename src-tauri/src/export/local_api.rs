@@ -0,0 +1,308 @@
+// シェルのプロンプトや tmux のステータス行から使うローカル API
+//
+// Unix ドメインソケット: 接続して1行目にコマンドを送る
+//   snapshot（何も送らずに閉じてもよい） → 最新の値を JSON で1行返して閉じる
+//   watch                                → 更新のたびに1行ずつ送る（NDJSON）
+//   例: nc -U "$HOME/Library/Application Support/com.fuji.vital-monitor/vital.sock" < /dev/null
+// HTTP（http_port を指定したときだけ 127.0.0.1 で受ける）: GET /snapshot, GET /watch
+//
+// 1行の中身は webview に送る `metrics://update` と同じ（classified にレベルが入る）
+use serde::Serialize;
+use std::net::Ipv4Addr;
+use std::os::unix::fs::{FileTypeExt, MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::Manager;
+use tokio::io::{AsyncBufReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, UnixListener};
+use tokio::sync::watch;
+use tokio::task::JoinSet;
+
+use crate::config::schema::LocalApiConfig;
+use crate::export::http;
+use crate::ipc::{MetricsUpdate, UpdateFeed};
+
+const SOCKET_FILE: &str = "vital.sock";
+// コマンドを送らないクライアントは snapshot とみなす
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(1);
+// 起動直後でまだ1件も無いときに待つ時間
+const FIRST_UPDATE_TIMEOUT: Duration = Duration::from_secs(3);
+
+type Feed = watch::Receiver<Option<MetricsUpdate>>;
+
+pub fn spawn_local_api(app: tauri::AppHandle) {
+    let handle = app.clone();
+    super::supervise(app, |cfg| cfg.export.local_api.clone(), move |cfg| {
+        let app = handle.clone();
+        async move { start(&app, cfg).await }
+    });
+}
+
+async fn start(app: &tauri::AppHandle, cfg: LocalApiConfig) -> Vec<tauri::async_runtime::JoinHandle<()>> {
+    let mut tasks = Vec::new();
+    if !cfg.enabled {
+        return tasks;
+    }
+    let feed = app.state::<UpdateFeed>().subscribe();
+
+    if let Some(path) = socket_path(app, &cfg) {
+        match bind_socket(&path) {
+            Ok(listener) => tasks.push(tauri::async_runtime::spawn(serve_socket(listener, path, feed.clone()))),
+            Err(e) => report(&format!("cannot listen on {}: {}", path.display(), e)),
+        }
+    }
+    if let Some(port) = cfg.http_port {
+        match TcpListener::bind((Ipv4Addr::LOCALHOST, port)).await {
+            Ok(listener) => tasks.push(tauri::async_runtime::spawn(serve_http(listener, feed))),
+            Err(e) => report(&format!("cannot listen on 127.0.0.1:{}: {}", port, e)),
+        }
+    }
+    tasks
+}
+
+fn report(message: &str) {
+    eprintln!("local api: {}", message);
+    crate::ui::notify::notify("Vital Monitor: local API", message);
+}
+
+pub fn socket_path(app: &tauri::AppHandle, cfg: &LocalApiConfig) -> Option<PathBuf> {
+    match &cfg.socket_path {
+        Some(path) => Some(PathBuf::from(path)),
+        None => app.path().app_data_dir().ok().map(|dir| dir.join(SOCKET_FILE)),
+    }
+}
+
+/// アプリ終了時に呼ぶ（プロセスごと終わるときはタスクが drop されず、SocketFile の後始末が走らない）
+pub fn remove_socket(app: &tauri::AppHandle, cfg: &LocalApiConfig) {
+    if !cfg.enabled {
+        return;
+    }
+    let Some(path) = socket_path(app, cfg) else { return; };
+    if std::fs::symlink_metadata(&path).is_ok_and(|m| m.file_type().is_socket()) {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+/// 自分のユーザーだけが読み書きできるソケットを作る
+fn bind_socket(path: &Path) -> std::io::Result<UnixListener> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    // 前回のソケットが残っていると bind できないので消す（ソケット以外は消さない）
+    match std::fs::symlink_metadata(path) {
+        Ok(meta) if meta.file_type().is_socket() => std::fs::remove_file(path)?,
+        Ok(_) => return Err(std::io::Error::new(std::io::ErrorKind::AlreadyExists, "path exists and is not a socket")),
+        Err(_) => {}
+    }
+    let listener = UnixListener::bind(path)?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    Ok(listener)
+}
+
+/// bind したソケットファイル。drop で消す
+/// 設定変更で張り直したときに新しいソケットを消さないよう、自分が作ったものか確かめる
+struct SocketFile {
+    path: PathBuf,
+    id: Option<(u64, u64)>,
+}
+
+impl SocketFile {
+    fn new(path: PathBuf) -> Self {
+        let id = file_id(&path);
+        Self { path, id }
+    }
+}
+
+impl Drop for SocketFile {
+    fn drop(&mut self) {
+        if self.id.is_some() && file_id(&self.path) == self.id {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+fn file_id(path: &Path) -> Option<(u64, u64)> {
+    std::fs::symlink_metadata(path).ok().map(|m| (m.dev(), m.ino()))
+}
+
+/// 止めるとき（このタスクが abort されたとき）は接続中のものも閉じ、ソケットを消す
+pub async fn serve_socket(listener: UnixListener, path: PathBuf, feed: Feed) {
+    let _socket = SocketFile::new(path);
+    let mut connections = JoinSet::new();
+    let mut backoff = http::AcceptBackoff::default();
+    loop {
+        let accepted = tokio::select! {
+            accepted = listener.accept() => accepted,
+            // 終わった接続を片付ける
+            Some(_) = connections.join_next() => continue,
+        };
+        let stream = match accepted {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                backoff.wait("local api", &e).await;
+                continue;
+            }
+        };
+        backoff.reset();
+        let feed = feed.clone();
+        connections.spawn(async move {
+            let (read, mut write) = stream.into_split();
+            let mut lines = BufReader::new(read).lines();
+            let command = tokio::time::timeout(COMMAND_TIMEOUT, lines.next_line())
+                .await
+                .ok()
+                .and_then(|line| line.ok().flatten())
+                .unwrap_or_default();
+
+            let _ = match command.trim() {
+                "" | "snapshot" => write_snapshot(&mut write, feed).await,
+                "watch" => write_stream(&mut write, feed).await,
+                other => write_line(&mut write, &ErrorLine { error: format!("unknown command: {}", other) }).await,
+            };
+        });
+    }
+}
+
+/// 止めるとき（このタスクが abort されたとき）は接続中のものも閉じる
+pub async fn serve_http(listener: TcpListener, feed: Feed) {
+    let mut connections = JoinSet::new();
+    let mut backoff = http::AcceptBackoff::default();
+    loop {
+        let accepted = tokio::select! {
+            accepted = listener.accept() => accepted,
+            Some(_) = connections.join_next() => continue,
+        };
+        let mut stream = match accepted {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                backoff.wait("local api", &e).await;
+                continue;
+            }
+        };
+        backoff.reset();
+        let mut feed = feed.clone();
+        connections.spawn(async move {
+            let Ok((method, path)) = http::read_request(&mut stream).await else { return; };
+            let _ = match (method.as_str(), path.as_str()) {
+                ("GET", "/snapshot") => match latest(&mut feed).await {
+                    Some(update) => {
+                        let body = serde_json::to_string(&update).unwrap_or_default() + "\n";
+                        http::write_response(&mut stream, "200 OK", "application/json", &body).await
+                    }
+                    None => http::write_response(&mut stream, "503 Service Unavailable", "text/plain; charset=utf-8", "no data yet\n").await,
+                },
+                ("GET", "/watch") => {
+                    // 長さは決まらないので、閉じるまでを本文にする
+                    let head = "HTTP/1.1 200 OK\r\nContent-Type: application/x-ndjson\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n";
+                    match stream.write_all(head.as_bytes()).await {
+                        Ok(()) => write_stream(&mut stream, feed).await,
+                        Err(e) => Err(e),
+                    }
+                }
+                _ => http::not_found(&mut stream).await,
+            };
+        });
+    }
+}
+
+#[derive(Serialize)]
+struct ErrorLine {
+    error: String,
+}
+
+async fn latest(feed: &mut Feed) -> Option<MetricsUpdate> {
+    let update = tokio::time::timeout(FIRST_UPDATE_TIMEOUT, feed.wait_for(Option::is_some)).await.ok()?.ok()?;
+    update.clone()
+}
+
+async fn write_snapshot<W: AsyncWrite + Unpin>(w: &mut W, mut feed: Feed) -> std::io::Result<()> {
+    match latest(&mut feed).await {
+        Some(update) => write_line(w, &update).await,
+        None => write_line(w, &ErrorLine { error: "no data yet".to_string() }).await,
+    }
+}
+
+/// 相手が閉じるまで送り続ける（遅いクライアントには途中の更新を飛ばして最新を送る）
+async fn write_stream<W: AsyncWrite + Unpin>(w: &mut W, mut feed: Feed) -> std::io::Result<()> {
+    // 接続した時点の値から送る
+    feed.mark_changed();
+    while feed.changed().await.is_ok() {
+        let update = feed.borrow_and_update().clone();
+        if let Some(update) = update {
+            write_line(w, &update).await?;
+        }
+    }
+    Ok(())
+}
+
+async fn write_line<W: AsyncWrite + Unpin, T: Serialize>(w: &mut W, value: &T) -> std::io::Result<()> {
+    let mut line = serde_json::to_string(value).map_err(std::io::Error::other)?;
+    line.push('\n');
+    w.write_all(line.as_bytes()).await?;
+    w.flush().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::history::Sample;
+    use crate::metrics::types::MetricsSnapshot;
+    use crate::ui::types::{ClassifiedSnapshot, Policies};
+    use tokio::net::UnixStream;
+
+    fn update(ts_ms: u64, cpu: f32) -> Option<MetricsUpdate> {
+        let snapshot = MetricsSnapshot { cpu_pct: Some(cpu), ..MetricsSnapshot::default() };
+        let classified = ClassifiedSnapshot::new(snapshot.clone(), &Policies::default());
+        Some(MetricsUpdate::new(Sample { ts_ms, snapshot }, classified))
+    }
+
+    #[tokio::test]
+    async fn serves_snapshot_and_stream_over_unix_socket() {
+        let path = std::env::temp_dir().join(format!("vital-monitor-api-{}.sock", std::process::id()));
+        let listener = bind_socket(&path).unwrap();
+        let feed = watch::Sender::new(update(1, 10.0));
+        let server = tokio::spawn(serve_socket(listener, path.clone(), feed.subscribe()));
+
+        let mut stream = UnixStream::connect(&path).await.unwrap();
+        stream.write_all(b"snapshot\n").await.unwrap();
+        let mut lines = BufReader::new(stream).lines();
+        let line: serde_json::Value = serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+        assert_eq!(line["ts_ms"], 1);
+        assert_eq!(line["classified"]["cpu"]["level"], "Safe");
+        assert!(lines.next_line().await.unwrap().is_none());
+
+        let mut stream = UnixStream::connect(&path).await.unwrap();
+        stream.write_all(b"watch\n").await.unwrap();
+        let mut lines = BufReader::new(stream).lines();
+        let first: serde_json::Value = serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+        assert_eq!(first["ts_ms"], 1);
+
+        feed.send_replace(update(2, 95.0));
+        let second: serde_json::Value = serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+        assert_eq!(second["ts_ms"], 2);
+        assert_eq!(second["classified"]["cpu"]["level"], "Critical");
+
+        // 止めたら watch 中の接続も閉じ、ソケットも消える
+        server.abort();
+        let _ = server.await;
+        let closed = tokio::time::timeout(Duration::from_secs(1), lines.next_line()).await.unwrap();
+        assert!(matches!(closed, Ok(None) | Err(_)));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn keeps_a_socket_rebound_by_someone_else() {
+        let path = std::env::temp_dir().join(format!("vital-monitor-api-rebind-{}.sock", std::process::id()));
+        let _first = std::os::unix::net::UnixListener::bind(&path).unwrap();
+        let old = SocketFile::new(path.clone());
+
+        // 張り直しで新しいソケットが同じ場所にできた後に古い方が drop される
+        std::fs::remove_file(&path).unwrap();
+        let _second = std::os::unix::net::UnixListener::bind(&path).unwrap();
+        drop(old);
+        assert!(path.exists());
+
+        drop(SocketFile::new(path.clone()));
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,58 @@
+pub mod history_csv;
+pub mod http;
+pub mod local_api;
+pub mod otlp;
+pub mod points;
+pub mod prometheus;
+pub mod statsd;
+
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::time::Duration;
+use tauri::async_runtime::JoinHandle;
+use tauri::Manager;
+
+use crate::ui::types::{TrayConfig, TrayUiState};
+
+/// Webhook や StatsD / OTLP のタグに使う
+pub fn host_name() -> String {
+    sysinfo::System::host_name().unwrap_or_else(|| "localhost".to_string())
+}
+
+/// host にホスト名を入れ、設定のタグを足す（設定側に host があればそちらを使う）
+pub fn host_tags(extra: &BTreeMap<String, String>) -> Vec<(String, String)> {
+    let mut tags = Vec::new();
+    if !extra.contains_key("host") {
+        tags.push(("host".to_string(), host_name()));
+    }
+    tags.extend(extra.iter().map(|(k, v)| (k.clone(), v.clone())));
+    tags
+}
+
+/// 設定の該当部分を1秒ごとに見て、変わったら動いているタスクを止めて start し直す
+pub fn supervise<C, F, Fut>(app: tauri::AppHandle, select: fn(&TrayConfig) -> C, start: F)
+where
+    C: Clone + PartialEq + Send + 'static,
+    F: Fn(C) -> Fut + Send + 'static,
+    Fut: Future<Output = Vec<JoinHandle<()>>> + Send,
+{
+    tauri::async_runtime::spawn(async move {
+        let mut tick = tokio::time::interval(Duration::from_secs(1));
+        let mut applied: Option<C> = None;
+        let mut running: Vec<JoinHandle<()>> = Vec::new();
+        loop {
+            tick.tick().await;
+
+            let cfg = select(&app.state::<TrayUiState>().config.lock().unwrap());
+            if applied.as_ref() == Some(&cfg) {
+                continue;
+            }
+            applied = Some(cfg.clone());
+
+            for task in running.drain(..) {
+                task.abort();
+            }
+            running = start(cfg).await;
+        }
+    });
+}
@@ -0,0 +1,135 @@
+// OpenTelemetry Collector に OTLP/HTTP（JSON エンコーディング）で一定間隔で送る
+//
+// すべて Gauge として送り、ホスト名と host_tags は resource の属性にする
+use serde_json::{json, Value};
+use std::time::Duration;
+use tauri::Manager;
+use tokio::sync::watch;
+
+use crate::config::schema::OtlpConfig;
+use crate::export::http::post_json;
+use crate::export::points::{self, Point};
+use crate::ipc::{MetricsUpdate, UpdateFeed};
+
+pub fn spawn_otlp_exporter(app: tauri::AppHandle) {
+    let handle = app.clone();
+    super::supervise(
+        app,
+        |cfg| (cfg.export.otlp.clone(), cfg.export.host_tags.clone()),
+        move |(cfg, tags)| {
+            let feed = handle.state::<UpdateFeed>().subscribe();
+            async move {
+                if !cfg.enabled {
+                    return Vec::new();
+                }
+                let tags = super::host_tags(&tags);
+                vec![tauri::async_runtime::spawn(run(cfg, tags, feed))]
+            }
+        },
+    );
+}
+
+async fn run(cfg: OtlpConfig, tags: Vec<(String, String)>, feed: watch::Receiver<Option<MetricsUpdate>>) {
+    let mut tick = tokio::time::interval(Duration::from_secs(cfg.flush_secs));
+    loop {
+        tick.tick().await;
+        let Some(update) = feed.borrow().clone() else { continue; };
+
+        let points = points::points(&update.snapshot, &update.classified);
+        let body = payload(&points, &tags, update.ts_ms).to_string();
+        match post_json(&cfg.endpoint, &body).await {
+            Ok(status) if (200..300).contains(&status) => {}
+            // 取りこぼしても次の周期で最新の値を送るので、やり直しはしない
+            Ok(status) => eprintln!("otlp {}: HTTP {}", cfg.endpoint, status),
+            Err(e) => eprintln!("otlp {}: {}", cfg.endpoint, e),
+        }
+    }
+}
+
+pub fn payload(points: &[Point], tags: &[(String, String)], ts_ms: u64) -> Value {
+    // OTLP の JSON では 64bit 整数を文字列で送る
+    let time = (ts_ms as u128 * 1_000_000).to_string();
+
+    let metrics: Vec<Value> = points::METRICS
+        .iter()
+        .filter_map(|(name, help)| {
+            let data_points: Vec<Value> = points
+                .iter()
+                .filter(|p| p.name == *name)
+                .map(|p| {
+                    let attributes: Vec<(&str, &str)> = p.labels.iter().map(|(k, v)| (*k, v.as_str())).collect();
+                    json!({
+                        "timeUnixNano": time,
+                        "asDouble": p.value,
+                        "attributes": attributes_json(&attributes),
+                    })
+                })
+                .collect();
+            (!data_points.is_empty()).then(|| {
+                json!({
+                    "name": format!("vital.{}", name),
+                    "description": help,
+                    "gauge": { "dataPoints": data_points },
+                })
+            })
+        })
+        .collect();
+
+    let resource: Vec<(&str, &str)> = std::iter::once(("service.name", "vital-monitor"))
+        .chain(tags.iter().map(|(k, v)| {
+            // OTel の慣習に合わせる
+            let key = if k == "host" { "host.name" } else { k.as_str() };
+            (key, v.as_str())
+        }))
+        .collect();
+
+    json!({
+        "resourceMetrics": [{
+            "resource": { "attributes": attributes_json(&resource) },
+            "scopeMetrics": [{
+                "scope": { "name": "vital-monitor", "version": env!("CARGO_PKG_VERSION") },
+                "metrics": metrics,
+            }],
+        }],
+    })
+}
+
+fn attributes_json(pairs: &[(&str, &str)]) -> Vec<Value> {
+    pairs
+        .iter()
+        .map(|(k, v)| json!({ "key": k, "value": { "stringValue": v } }))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::types::{MetricsSnapshot, ProbeResult};
+    use crate::ui::types::{ClassifiedSnapshot, Policies};
+
+    #[test]
+    fn builds_gauges_with_resource_attributes() {
+        let snapshot = MetricsSnapshot {
+            cpu_pct: Some(12.5),
+            nw_probes: vec![ProbeResult { target: "1.1.1.1".into(), ms: Some(20.0) }],
+            ..MetricsSnapshot::default()
+        };
+        let points = points::points(&snapshot, &ClassifiedSnapshot::new(snapshot.clone(), &Policies::default()));
+        let tags = vec![("host".to_string(), "build-01".to_string()), ("team".to_string(), "infra".to_string())];
+        let body = payload(&points, &tags, 1_700_000_000_000);
+
+        let resource = &body["resourceMetrics"][0];
+        assert_eq!(resource["resource"]["attributes"][1], json!({ "key": "host.name", "value": { "stringValue": "build-01" } }));
+
+        let metrics = resource["scopeMetrics"][0]["metrics"].as_array().unwrap();
+        assert_eq!(metrics[0]["name"], "vital.cpu_percent");
+        let cpu = &metrics[0]["gauge"]["dataPoints"][0];
+        assert_eq!(cpu["asDouble"], 12.5);
+        assert_eq!(cpu["timeUnixNano"], "1700000000000000000");
+
+        let latency = metrics.iter().find(|m| m["name"] == "vital.nw_latency_ms").unwrap();
+        assert_eq!(latency["gauge"]["dataPoints"][0]["attributes"][0]["value"]["stringValue"], "1.1.1.1");
+        // 値の無いメトリクスは送らない
+        assert!(metrics.iter().all(|m| m["name"] != "vital.mem_pressure_percent"));
+    }
+}
@@ -0,0 +1,70 @@
+// 外部に出すメトリクスの一覧（Prometheus / StatsD / OTLP で共通）
+//
+// 名前に接頭辞は付けない。出力ごとに "vital_" や "vital." を付ける
+use crate::metrics::types::MetricsSnapshot;
+use crate::ui::types::{AlertLevel, ClassifiedSnapshot, MetricKind};
+
+/// (名前, 説明) の順番どおりに出力する
+pub const METRICS: [(&str, &str); 8] = [
+    ("cpu_percent", "CPU usage in percent."),
+    ("mem_pressure_percent", "Memory pressure in percent."),
+    ("nw_latency_ms", "Round-trip time per probe target in milliseconds."),
+    ("nw_probe_success", "Whether the last probe of the target got a reply (1) or not (0)."),
+    ("nw_latency_p95_ms", "95th percentile of recent network latency in milliseconds."),
+    ("process_cpu_percent", "CPU usage of a watched process in percent of one core (summed over matches)."),
+    ("process_rss_bytes", "Resident memory of a watched process in bytes (summed over matches)."),
+    ("alert_level", "Alert level per metric, process or derived alert: 0=Safe, 1=Normal, 2=Warning, 3=Critical."),
+];
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Point {
+    pub name: &'static str,
+    pub labels: Vec<(&'static str, String)>,
+    pub value: f64,
+}
+
+/// 値が取れていない項目は含めない
+pub fn points(snapshot: &MetricsSnapshot, classified: &ClassifiedSnapshot) -> Vec<Point> {
+    let mut out = Vec::new();
+    let mut push = |name: &'static str, labels: Vec<(&'static str, String)>, value: Option<f64>| {
+        if let Some(value) = value {
+            out.push(Point { name, labels, value });
+        }
+    };
+
+    push("cpu_percent", vec![], snapshot.cpu_pct.map(f64::from));
+    push("mem_pressure_percent", vec![], snapshot.mem_pressure_pct.map(f64::from));
+    for probe in &snapshot.nw_probes {
+        push("nw_latency_ms", vec![("target", probe.target.clone())], probe.ms);
+    }
+    for probe in &snapshot.nw_probes {
+        let success = if probe.ms.is_some() { 1.0 } else { 0.0 };
+        push("nw_probe_success", vec![("target", probe.target.clone())], Some(success));
+    }
+    push("nw_latency_p95_ms", vec![], snapshot.nw_p95_ms);
+    for p in &snapshot.procs {
+        push("process_cpu_percent", vec![("process", p.name.clone())], p.cpu_pct.map(f64::from));
+        push("process_rss_bytes", vec![("process", p.name.clone())], p.rss_bytes.map(|b| b as f64));
+    }
+    for kind in MetricKind::ALL {
+        let level = classified.level_of(kind).map(|l| level_value(l) as f64);
+        push("alert_level", vec![("metric", kind.key().to_string())], level);
+    }
+    for p in &classified.procs {
+        push("alert_level", vec![("metric", p.cpu_key())], p.cpu.map(|m| level_value(m.level) as f64));
+        push("alert_level", vec![("metric", p.rss_key())], p.rss_gb.map(|m| level_value(m.level) as f64));
+    }
+    for d in &classified.derived {
+        push("alert_level", vec![("metric", d.name.clone())], Some(level_value(d.level) as f64));
+    }
+    out
+}
+
+pub fn level_value(level: AlertLevel) -> u8 {
+    match level {
+        AlertLevel::Safe => 0,
+        AlertLevel::Normal => 1,
+        AlertLevel::Warning => 2,
+        AlertLevel::Critical => 3,
+    }
+}
@@ -0,0 +1,159 @@
+// Prometheus の `/metrics`（テキスト形式）を 127.0.0.1 で返す
+//
+// 有効/無効とポートは config.export.prometheus。設定が変わったら待ち受けを張り直す
+use std::fmt::Write;
+use std::net::Ipv4Addr;
+use tauri::Manager;
+use tokio::net::TcpListener;
+use tokio::sync::watch;
+use tokio::task::JoinSet;
+
+use crate::export::{http, points};
+use crate::ipc::{MetricsUpdate, UpdateFeed};
+use crate::metrics::types::MetricsSnapshot;
+use crate::ui::types::ClassifiedSnapshot;
+
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+pub fn spawn_prometheus_exporter(app: tauri::AppHandle) {
+    let handle = app.clone();
+    super::supervise(app, |cfg| cfg.export.prometheus.clone(), move |cfg| {
+        let feed = handle.state::<UpdateFeed>().subscribe();
+        async move {
+            if !cfg.enabled {
+                return Vec::new();
+            }
+            match TcpListener::bind((Ipv4Addr::LOCALHOST, cfg.port)).await {
+                Ok(listener) => vec![tauri::async_runtime::spawn(serve(listener, feed))],
+                Err(e) => {
+                    eprintln!("prometheus exporter: cannot listen on 127.0.0.1:{}: {}", cfg.port, e);
+                    crate::ui::notify::notify("Vital Monitor: Prometheus exporter", &e.to_string());
+                    Vec::new()
+                }
+            }
+        }
+    });
+}
+
+/// レベルはサンプラーが付けたもの（設定変更にも追従する）を最新の更新から取る
+pub async fn serve(listener: TcpListener, feed: watch::Receiver<Option<MetricsUpdate>>) {
+    // 張り直しでこのタスクが abort されたら接続中のものも一緒に閉じる
+    let mut connections = JoinSet::new();
+    let mut backoff = http::AcceptBackoff::default();
+    loop {
+        let accepted = tokio::select! {
+            accepted = listener.accept() => accepted,
+            Some(_) = connections.join_next() => continue,
+        };
+        let mut stream = match accepted {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                backoff.wait("prometheus exporter", &e).await;
+                continue;
+            }
+        };
+        backoff.reset();
+        let feed = feed.clone();
+        connections.spawn(async move {
+            let Ok((method, path)) = http::read_request(&mut stream).await else { return; };
+            let _ = if method == "GET" && path == "/metrics" {
+                let body = match feed.borrow().clone() {
+                    Some(update) => render(&update.snapshot, &update.classified),
+                    None => render(&MetricsSnapshot::default(), &ClassifiedSnapshot::default()),
+                };
+                http::write_response(&mut stream, "200 OK", CONTENT_TYPE, &body).await
+            } else {
+                http::not_found(&mut stream).await
+            };
+        });
+    }
+}
+
+/// 値が取れていない項目は行ごと出さない
+pub fn render(snapshot: &MetricsSnapshot, classified: &ClassifiedSnapshot) -> String {
+    let points = points::points(snapshot, classified);
+    let mut out = String::new();
+    for (name, help) in points::METRICS {
+        let _ = writeln!(out, "# HELP vital_{} {}", name, help);
+        let _ = writeln!(out, "# TYPE vital_{} gauge", name);
+        for point in points.iter().filter(|p| p.name == name) {
+            let _ = write!(out, "vital_{}", name);
+            if !point.labels.is_empty() {
+                let labels: Vec<String> = point
+                    .labels
+                    .iter()
+                    .map(|(k, v)| format!("{}=\"{}\"", k, escape_label(v)))
+                    .collect();
+                let _ = write!(out, "{{{}}}", labels.join(","));
+            }
+            let _ = writeln!(out, " {}", point.value);
+        }
+    }
+    out
+}
+
+fn escape_label(v: &str) -> String {
+    v.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::history::Sample;
+    use crate::metrics::types::{ProbeResult, ProcessSample};
+    use crate::ui::types::Policies;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    fn snapshot() -> MetricsSnapshot {
+        MetricsSnapshot {
+            cpu_pct: Some(95.0),
+            mem_pressure_pct: None,
+            nw_ms: Some(12.5),
+            nw_p95_ms: Some(20.0),
+            nw_probes: vec![
+                ProbeResult { target: "1.1.1.1".into(), ms: Some(12.5) },
+                ProbeResult { target: "10.0.0.\"1".into(), ms: None },
+            ],
+            disk_used_pct: None,
+            procs: vec![ProcessSample { name: "node".into(), count: 2, cpu_pct: Some(12.5), rss_bytes: Some(1 << 30) }],
+        }
+    }
+
+    #[test]
+    fn renders_exposition_format() {
+        let snap = snapshot();
+        let text = render(&snap, &ClassifiedSnapshot::new(snap.clone(), &Policies::default()));
+
+        assert!(text.contains("# TYPE vital_cpu_percent gauge\nvital_cpu_percent 95\n"));
+        assert!(!text.contains("\nvital_mem_pressure_percent "));
+        assert!(text.contains("vital_nw_latency_ms{target=\"1.1.1.1\"} 12.5\n"));
+        assert!(text.contains("vital_nw_probe_success{target=\"10.0.0.\\\"1\"} 0\n"));
+        assert!(text.contains("vital_process_rss_bytes{process=\"node\"} 1073741824\n"));
+        assert!(text.contains("vital_alert_level{metric=\"cpu\"} 3\n"));
+        assert!(!text.contains("vital_alert_level{metric=\"mem\"}"));
+    }
+
+    #[tokio::test]
+    async fn serves_metrics_over_http() {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let classified = ClassifiedSnapshot::new(snapshot(), &Policies::default());
+        let feed = watch::Sender::new(Some(MetricsUpdate::new(Sample { ts_ms: 1, snapshot: snapshot() }, classified)));
+        tokio::spawn(serve(listener, feed.subscribe()));
+
+        let get = |path: &'static str| async move {
+            let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+            let request = format!("GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path);
+            stream.write_all(request.as_bytes()).await.unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+            response
+        };
+
+        let response = get("/metrics").await;
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("vital_cpu_percent 95\n"));
+
+        assert!(get("/").await.starts_with("HTTP/1.1 404"));
+    }
+}
@@ -0,0 +1,159 @@
+// StatsD（UDP）に一定間隔で最新の値を送る
+//
+// 1行 = "vital.cpu_percent:12.5|g|#host:mbp,target:1.1.1.1"（タグは DogStatsD 形式）
+use std::time::Duration;
+use tauri::Manager;
+use tokio::net::UdpSocket;
+use tokio::sync::watch;
+
+use crate::config::schema::StatsdConfig;
+use crate::export::points::{self, Point};
+use crate::ipc::{MetricsUpdate, UpdateFeed};
+
+// 経路の途中で分割されない大きさに収める
+const MAX_DATAGRAM_BYTES: usize = 1432;
+
+pub fn spawn_statsd_exporter(app: tauri::AppHandle) {
+    let handle = app.clone();
+    super::supervise(
+        app,
+        |cfg| (cfg.export.statsd.clone(), cfg.export.host_tags.clone()),
+        move |(cfg, tags)| {
+            let feed = handle.state::<UpdateFeed>().subscribe();
+            async move {
+                if !cfg.enabled {
+                    return Vec::new();
+                }
+                let tags = super::host_tags(&tags);
+                vec![tauri::async_runtime::spawn(run(cfg, tags, feed))]
+            }
+        },
+    );
+}
+
+pub async fn run(cfg: StatsdConfig, tags: Vec<(String, String)>, feed: watch::Receiver<Option<MetricsUpdate>>) {
+    let socket = match connect(&cfg.address).await {
+        Ok(socket) => socket,
+        Err(e) => {
+            eprintln!("statsd: {}: {}", cfg.address, e);
+            crate::ui::notify::notify("Vital Monitor: StatsD exporter", &e.to_string());
+            return;
+        }
+    };
+
+    let mut tick = tokio::time::interval(Duration::from_secs(cfg.flush_secs));
+    loop {
+        tick.tick().await;
+        let Some(update) = feed.borrow().clone() else { continue; };
+
+        let points = points::points(&update.snapshot, &update.classified);
+        for datagram in datagrams(&lines(&cfg.prefix, &points, &tags)) {
+            if let Err(e) = socket.send(datagram.as_bytes()).await {
+                // 受け手が居ないだけのことが多いので、次の周期でまた送る
+                eprintln!("statsd: {}: {}", cfg.address, e);
+                break;
+            }
+        }
+    }
+}
+
+async fn connect(address: &str) -> std::io::Result<UdpSocket> {
+    let target = tokio::net::lookup_host(address)
+        .await?
+        .next()
+        .ok_or_else(|| std::io::Error::other("address did not resolve"))?;
+    let socket = UdpSocket::bind(if target.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" }).await?;
+    socket.connect(target).await?;
+    Ok(socket)
+}
+
+pub fn lines(prefix: &str, points: &[Point], tags: &[(String, String)]) -> Vec<String> {
+    points
+        .iter()
+        .map(|p| {
+            let all_tags: Vec<String> = tags
+                .iter()
+                .map(|(k, v)| (k.as_str(), v.as_str()))
+                .chain(p.labels.iter().map(|(k, v)| (*k, v.as_str())))
+                .map(|(k, v)| format!("{}:{}", sanitize(k), sanitize(v)))
+                .collect();
+            let mut line = format!("{}{}:{}|g", prefix, p.name, p.value);
+            if !all_tags.is_empty() {
+                line.push_str("|#");
+                line.push_str(&all_tags.join(","));
+            }
+            line
+        })
+        .collect()
+}
+
+/// 改行区切りで1パケットに詰められるだけ詰める
+pub fn datagrams(lines: &[String]) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for line in lines {
+        match out.last_mut() {
+            Some(current) if current.len() + 1 + line.len() <= MAX_DATAGRAM_BYTES => {
+                current.push('\n');
+                current.push_str(line);
+            }
+            _ => out.push(line.clone()),
+        }
+    }
+    out
+}
+
+// タグの区切りに使われる文字を潰す
+fn sanitize(s: &str) -> String {
+    s.chars()
+        .map(|c| if matches!(c, ',' | '|' | '#' | '\n') || c.is_whitespace() { '_' } else { c })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::history::Sample;
+    use crate::metrics::types::{MetricsSnapshot, ProbeResult};
+    use crate::ui::types::{ClassifiedSnapshot, Policies};
+
+    fn update() -> MetricsUpdate {
+        let snapshot = MetricsSnapshot {
+            cpu_pct: Some(12.5),
+            nw_ms: Some(20.0),
+            nw_probes: vec![ProbeResult { target: "1.1.1.1".into(), ms: Some(20.0) }],
+            ..MetricsSnapshot::default()
+        };
+        let classified = ClassifiedSnapshot::new(snapshot.clone(), &Policies::default());
+        MetricsUpdate::new(Sample { ts_ms: 1, snapshot }, classified)
+    }
+
+    #[test]
+    fn formats_gauges_with_tags() {
+        let u = update();
+        let tags = vec![("host".to_string(), "build 01".to_string())];
+        let lines = lines("vital.", &points::points(&u.snapshot, &u.classified), &tags);
+
+        assert_eq!(lines[0], "vital.cpu_percent:12.5|g|#host:build_01");
+        assert!(lines.contains(&"vital.nw_latency_ms:20|g|#host:build_01,target:1.1.1.1".to_string()));
+        assert!(lines.contains(&"vital.alert_level:0|g|#host:build_01,metric:cpu".to_string()));
+
+        let many: Vec<String> = (0..200).map(|i| format!("vital.x:{}|g", i)).collect();
+        let packed = datagrams(&many);
+        assert!(packed.len() > 1);
+        assert!(packed.iter().all(|d| d.len() <= MAX_DATAGRAM_BYTES));
+        assert_eq!(packed.join("\n").lines().count(), 200);
+    }
+
+    #[tokio::test]
+    async fn pushes_latest_update_over_udp() {
+        let receiver = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let cfg = StatsdConfig { enabled: true, address: receiver.local_addr().unwrap().to_string(), ..StatsdConfig::default() };
+        let feed = watch::Sender::new(Some(update()));
+        tokio::spawn(run(cfg, vec![], feed.subscribe()));
+
+        let mut buf = [0u8; MAX_DATAGRAM_BYTES];
+        let n = receiver.recv(&mut buf).await.unwrap();
+        let text = std::str::from_utf8(&buf[..n]).unwrap();
+        assert!(text.starts_with("vital.cpu_percent:12.5|g\n"));
+    }
+}
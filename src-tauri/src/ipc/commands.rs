@@ -0,0 +1,138 @@
+// webview から invoke で呼ぶコマンド
+use std::sync::Mutex;
+use tauri::State;
+
+use crate::alerts::log::AlertLog;
+use crate::alerts::quiet::DoNotDisturb;
+use crate::alerts::transitions::Transition;
+use crate::metrics::history::{HistoryRange, Sample, SharedHistory};
+use crate::metrics::types::{MetricsSnapshot, SharedMetrics};
+use crate::ui::types::{ClassifiedSnapshot, TrayConfig, TrayUiState};
+use crate::config::migrate::CURRENT_VERSION;
+use crate::config::profiles::{self, ProfileState};
+use crate::config::store::ConfigIssues;
+
+#[tauri::command]
+pub async fn get_snapshot(metrics: State<'_, SharedMetrics>) -> Result<MetricsSnapshot, String> {
+    Ok(metrics.read().await.clone())
+}
+
+#[tauri::command]
+pub async fn get_classified_snapshot(
+    app: tauri::AppHandle,
+    metrics: State<'_, SharedMetrics>,
+    ui_state: State<'_, TrayUiState>,
+) -> Result<ClassifiedSnapshot, String> {
+    if let Some(update) = crate::ipc::latest_update(&app) {
+        return Ok(update.classified);
+    }
+    // サンプラーの初回より前は閾値だけで付ける
+    let policies = ui_state.config.lock().unwrap().policies();
+    let snap = metrics.read().await.clone();
+    Ok(ClassifiedSnapshot::new(snap, &policies))
+}
+
+#[tauri::command]
+pub fn get_config(ui_state: State<'_, TrayUiState>) -> TrayConfig {
+    ui_state.config.lock().unwrap().clone()
+}
+
+/// 検証に通らなければ適用せずに理由を返す
+#[tauri::command]
+pub fn set_config(app: tauri::AppHandle, config: TrayConfig) -> Result<(), Vec<String>> {
+    let config = TrayConfig { version: CURRENT_VERSION, ..config };
+    config.validate().map_err(|e| e.messages())?;
+    crate::config::store::apply(&app, config);
+    Ok(())
+}
+
+/// 起動時の読み込みで見つかった問題（設定画面に表示する）
+#[tauri::command]
+pub fn get_config_issues(issues: State<'_, ConfigIssues>) -> Vec<String> {
+    issues.0.lock().unwrap().clone()
+}
+
+#[tauri::command]
+pub fn get_default_config() -> TrayConfig {
+    TrayConfig::default()
+}
+
+#[tauri::command]
+pub async fn get_history(history: State<'_, SharedHistory>, range: HistoryRange) -> Result<Vec<Sample>, String> {
+    Ok(history.read().await.range(range))
+}
+
+#[tauri::command]
+pub fn list_profiles(profiles: State<'_, ProfileState>) -> Vec<String> {
+    profiles.names()
+}
+
+#[tauri::command]
+pub fn get_active_profile(profiles: State<'_, ProfileState>) -> Option<String> {
+    profiles.active()
+}
+
+#[tauri::command]
+pub fn switch_profile(app: tauri::AppHandle, name: String) -> Result<(), Vec<String>> {
+    profiles::switch(&app, &name).map_err(|e| e.messages())
+}
+
+/// 今の設定を名前付きで保存する（同じ名前があれば上書き）
+#[tauri::command]
+pub fn export_profile(app: tauri::AppHandle, name: String) -> Result<String, Vec<String>> {
+    profiles::export_current(&app, &name).map_err(|e| e.messages())
+}
+
+/// 今の設定を path に書き出す（import_profile でそのまま取り込める）
+#[tauri::command]
+pub fn export_profile_file(app: tauri::AppHandle, path: String) -> Result<(), Vec<String>> {
+    profiles::export_to_file(&app, std::path::Path::new(&path)).map_err(|e| e.messages())
+}
+
+/// 取り込んだプロファイルの名前を返す（切り替えはしない）
+#[tauri::command]
+pub fn import_profile(app: tauri::AppHandle, path: String) -> Result<String, Vec<String>> {
+    profiles::import(&app, std::path::Path::new(&path)).map_err(|e| e.messages())
+}
+
+/// 直近 minutes 分の履歴を CSV で書き出し、書いた行数を返す
+#[tauri::command]
+pub async fn export_history(app: tauri::AppHandle, minutes: u64, path: String) -> Result<usize, String> {
+    crate::export::history_csv::export_history(&app, minutes, std::path::Path::new(&path)).await
+}
+
+/// 記録したレベルの遷移（新しい順に最大 limit 件）
+#[tauri::command]
+pub fn get_alert_log(log: State<'_, Mutex<AlertLog>>, limit: usize) -> Vec<Transition> {
+    log.lock().unwrap().recent(limit)
+}
+
+/// metric を省略すると Warning 以上のものをすべて確認済みにする
+#[tauri::command]
+pub fn acknowledge_alert(app: tauri::AppHandle, log: State<'_, Mutex<AlertLog>>, metric: Option<String>) -> bool {
+    let acked = {
+        let mut log = log.lock().unwrap();
+        match metric {
+            Some(metric) => log.acknowledge(&metric),
+            None => {
+                log.acknowledge_all();
+                true
+            }
+        }
+    };
+    crate::ui::tray::refresh_alerts_menu(&app);
+    acked
+}
+
+#[tauri::command]
+pub fn clear_alert_log(app: tauri::AppHandle, log: State<'_, Mutex<AlertLog>>) {
+    log.lock().unwrap().clear();
+    crate::ui::tray::refresh_alerts_menu(&app);
+}
+
+/// until_ms（UNIX epoch ミリ秒）まで通知と Webhook を止める。None で解除
+#[tauri::command]
+pub fn set_do_not_disturb(app: tauri::AppHandle, dnd: State<'_, DoNotDisturb>, until_ms: Option<u64>) {
+    dnd.set(until_ms);
+    crate::ui::tray::refresh_dnd_menu(&app);
+}
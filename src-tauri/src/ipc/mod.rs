@@ -0,0 +1,53 @@
+pub mod commands;
+
+use serde::Serialize;
+use tauri::{Emitter, Manager};
+use tokio::sync::watch;
+
+use crate::metrics::history::Sample;
+use crate::metrics::types::MetricsSnapshot;
+use crate::ui::types::ClassifiedSnapshot;
+
+pub const METRICS_UPDATE_EVENT: &str = "metrics://update";
+
+/// `metrics://update` のペイロード
+#[derive(Clone, Debug, Serialize)]
+pub struct MetricsUpdate {
+    pub ts_ms: u64,
+    pub snapshot: MetricsSnapshot,
+    pub classified: ClassifiedSnapshot,
+}
+
+impl MetricsUpdate {
+    /// classified は Evaluator で付けたもの（閾値だけでなく継続時間・ルールも含む）
+    pub fn new(sample: Sample, classified: ClassifiedSnapshot) -> Self {
+        Self { ts_ms: sample.ts_ms, snapshot: sample.snapshot, classified }
+    }
+}
+
+/// webview 以外（ローカル API など）に最新の更新を配る
+pub struct UpdateFeed(watch::Sender<Option<MetricsUpdate>>);
+
+impl Default for UpdateFeed {
+    fn default() -> Self {
+        Self(watch::Sender::new(None))
+    }
+}
+
+impl UpdateFeed {
+    pub fn subscribe(&self) -> watch::Receiver<Option<MetricsUpdate>> {
+        self.0.subscribe()
+    }
+}
+
+pub fn emit_update(app: &tauri::AppHandle, update: MetricsUpdate) {
+    if let Some(feed) = app.try_state::<UpdateFeed>() {
+        feed.0.send_replace(Some(update.clone()));
+    }
+    let _ = app.emit(METRICS_UPDATE_EVENT, update);
+}
+
+/// 最新の更新。まだ1回も無ければ None
+pub fn latest_update(app: &tauri::AppHandle) -> Option<MetricsUpdate> {
+    app.try_state::<UpdateFeed>().and_then(|feed| feed.0.borrow().clone())
+}
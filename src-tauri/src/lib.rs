@@ -1,56 +1,98 @@
 use tauri::tray::TrayIcon;
 use std::sync::{Mutex, Arc};
-use std::path::PathBuf;
 use tokio::sync::RwLock;
 use tauri::Manager;
 use tauri::ActivationPolicy;
 
+mod alerts;
+pub mod cli;
+mod config;
+mod export;
+mod ipc;
 mod mac_metrics;
 mod metrics;
 mod ui;
 mod updater;
 
 use crate::metrics::types::{SharedMetrics, MetricsSnapshot};
-use crate::ui::types::{TrayConfig, DisplayMode};
+use crate::metrics::history::{History, SharedHistory, HISTORY_CAPACITY};
+use crate::config::profiles::ProfileState;
+use crate::config::store::ConfigIssues;
+use crate::config::writer::ConfigWriter;
+use crate::metrics::service::SamplingControl;
 
 pub struct TrayState {
     pub tray: Mutex<TrayIcon>,
 }
 
-fn load_config(app: &tauri::App) -> Option<TrayConfig> {
-    let dir: PathBuf = app.path().app_config_dir().ok()?;
-    let path = dir.join("tray_config.json");
-    let text = std::fs::read_to_string(path).ok()?;
-    serde_json::from_str(&text).ok()
-}
-
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     
     tauri::Builder::default()
         .setup(|app| {
-            let initial_cfg = load_config(app)
-                .unwrap_or(TrayConfig {
-                    show_cpu: true,
-                    show_mem: true,
-                    show_nw: true,
-                    mode: DisplayMode::List,
-                    is_alert: true,
-                });
+            let (initial_cfg, config_issues) = config::store::load_initial(app.handle());
+            app.manage(ConfigWriter::spawn(app.handle()));
+            let sampling = SamplingControl::new(&initial_cfg);
+            let sampling_rx = sampling.subscribe();
+            let processes_rx = sampling.subscribe_processes();
+            app.manage(ProfileState::load(app.handle()));
+            let alert_log_path = app.path().app_data_dir().ok().map(|dir| dir.join(alerts::log::LOG_FILE));
+            app.manage(Mutex::new(alerts::log::AlertLog::load(alert_log_path)));
+            app.manage(alerts::quiet::DoNotDisturb::default());
 
             ui::tray::build_tray(app, initial_cfg)?;
 
             let metrics: SharedMetrics = Arc::new(RwLock::new(MetricsSnapshot::default()));
+            let history: SharedHistory = Arc::new(RwLock::new(History::new(HISTORY_CAPACITY)));
+            app.manage(metrics.clone());
+            app.manage(history.clone());
+            app.manage(sampling);
+            app.manage(ipc::UpdateFeed::default());
         
             // #[cfg(target_os = "macos")]
             // spawn_tray_updater(app.handle().clone());
-            metrics::service::spawn_metric_tasks(metrics.clone());
-            updater::tray_updater::spawn_tray_renderer(app.handle().clone(), metrics);
+            metrics::service::spawn_metric_tasks(metrics.clone(), sampling_rx, processes_rx);
+            metrics::service::spawn_sampler(app.handle().clone(), metrics.clone(), history);
+            export::prometheus::spawn_prometheus_exporter(app.handle().clone());
+            export::local_api::spawn_local_api(app.handle().clone());
+            export::statsd::spawn_statsd_exporter(app.handle().clone());
+            export::otlp::spawn_otlp_exporter(app.handle().clone());
+            alerts::spawn_alert_engine(app.handle().clone());
+            updater::tray_updater::spawn_tray_renderer(app.handle().clone());
+            config::auto_switch::spawn_profile_switcher(app.handle().clone());
 
             // Dockに表示しない
             app.set_activation_policy(ActivationPolicy::Accessory);
+
+            // 設定ファイルに問題があれば設定画面を開いて知らせる
+            let has_issues = !config_issues.is_empty();
+            app.manage(ConfigIssues(Mutex::new(config_issues)));
+            if has_issues {
+                ui::settings::open(app.handle());
+            }
+            config::watch::spawn_config_watcher(app.handle().clone());
             Ok(())
         })
+        .invoke_handler(tauri::generate_handler![
+            ipc::commands::get_snapshot,
+            ipc::commands::get_classified_snapshot,
+            ipc::commands::get_config,
+            ipc::commands::set_config,
+            ipc::commands::get_config_issues,
+            ipc::commands::get_default_config,
+            ipc::commands::get_history,
+            ipc::commands::export_history,
+            ipc::commands::list_profiles,
+            ipc::commands::get_active_profile,
+            ipc::commands::switch_profile,
+            ipc::commands::export_profile,
+            ipc::commands::export_profile_file,
+            ipc::commands::import_profile,
+            ipc::commands::get_alert_log,
+            ipc::commands::acknowledge_alert,
+            ipc::commands::clear_alert_log,
+            ipc::commands::set_do_not_disturb,
+        ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
@@ -42,15 +42,31 @@ fn get_cpu() -> f32 {
     sys.global_cpu_info().cpu_usage()
 }
 
+// 起動ボリューム（APFS ではデータ側の /System/Volumes/Data）の使用率
+pub async fn read_disk_used_pct() -> Result<f32, Box<dyn std::error::Error + Send + Sync>> {
+    use sysinfo::Disks;
+
+    let disks = Disks::new_with_refreshed_list();
+    let disk = ["/System/Volumes/Data", "/"]
+        .iter()
+        .find_map(|mount| disks.list().iter().find(|d| d.mount_point() == std::path::Path::new(mount)))
+        .ok_or("boot volume not found")?;
+    let total = disk.total_space();
+    if total == 0 {
+        return Err("boot volume has no capacity".into());
+    }
+    let used = total.saturating_sub(disk.available_space());
+    Ok((used as f64 / total as f64 * 100.0) as f32)
+}
 
-pub async fn network_latency_ms() -> Result<f64, Box<dyn std::error::Error + Send + Sync>> {
-    // DNS遅延を排除したいので IP 直指定が無難（Cloudflare）
-    let host = "1.1.1.1";
 
+// DNS遅延を排除したいので host は IP 直指定が無難（既定は Cloudflare の 1.1.1.1）
+pub async fn network_latency_ms(host: &str) -> Result<f64, Box<dyn std::error::Error + Send + Sync>> {
     // ping が詰まるケース対策：外側で timeout をかける（OS差を吸収）
     let fut = Command::new("ping")
         .arg("-n")          // 逆引きDNSを抑制（macOSで有効）
         .arg("-c").arg("1") // 1回だけ
+        .arg("--")          // host をオプションとして読ませない
         .arg(host)
         .output();
 
@@ -95,3 +111,43 @@ fn tcp_latency() -> Option<u128> {
 
     Some(start.elapsed().as_millis())
 }
+
+// ---- プロファイルの自動切り替え用（今いるネットワークと電源） ----
+
+type EnvResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+async fn command_stdout(program: &str, args: &[&str]) -> EnvResult<String> {
+    let output = timeout(Duration::from_secs(2), Command::new(program).args(args).output())
+        .await
+        .map_err(|_| format!("{} timeout", program))??;
+    if !output.status.success() {
+        return Err(format!("{} failed", program).into());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+// 例: "    gateway: 192.168.1.1"（既定経路が無ければ失敗する）
+pub async fn default_gateway() -> EnvResult<Option<String>> {
+    let stdout = command_stdout("route", &["-n", "get", "default"]).await?;
+    Ok(stdout
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("gateway:"))
+        .map(|gw| gw.trim().to_string()))
+}
+
+// Wi-Fi は en0 を見る
+// 例: "Current Wi-Fi Network: Office-5G" / 未接続なら "You are not associated with an AirPort network."
+pub async fn wifi_ssid() -> EnvResult<Option<String>> {
+    let stdout = command_stdout("networksetup", &["-getairportnetwork", "en0"]).await?;
+    Ok(stdout
+        .lines()
+        .find_map(|line| line.split_once("Network: "))
+        .map(|(_, ssid)| ssid.trim().to_string())
+        .filter(|ssid| !ssid.is_empty()))
+}
+
+// 例: "Now drawing from 'Battery Power'"
+pub async fn on_battery_power() -> EnvResult<bool> {
+    let stdout = command_stdout("pmset", &["-g", "batt"]).await?;
+    Ok(stdout.contains("'Battery Power'"))
+}
@@ -0,0 +1,138 @@
+// 取得したスナップショットの履歴（メモリ上のリングバッファ）
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+
+use crate::metrics::types::MetricsSnapshot;
+
+// 1秒ごとに1件 → 1時間分
+pub const HISTORY_CAPACITY: usize = 60 * 60;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Sample {
+    // UNIX epoch からのミリ秒
+    pub ts_ms: u64,
+    pub snapshot: MetricsSnapshot,
+}
+
+impl Sample {
+    pub fn now(snapshot: MetricsSnapshot) -> Self {
+        Self { ts_ms: now_ms(), snapshot }
+    }
+}
+
+pub fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// 取得する期間の指定
+/// - `{"Last": 300}` 直近300秒
+/// - `{"Between": {"from_ms": .., "to_ms": ..}}`
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum HistoryRange {
+    Last(u64),
+    Between { from_ms: u64, to_ms: u64 },
+}
+
+pub struct History {
+    samples: VecDeque<Sample>,
+    capacity: usize,
+}
+
+impl History {
+    pub fn new(capacity: usize) -> Self {
+        Self { samples: VecDeque::with_capacity(capacity), capacity }
+    }
+
+    pub fn push(&mut self, sample: Sample) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    pub fn range(&self, range: HistoryRange) -> Vec<Sample> {
+        let (from, to) = match range {
+            HistoryRange::Last(secs) => (now_ms().saturating_sub(secs.saturating_mul(1000)), u64::MAX),
+            HistoryRange::Between { from_ms, to_ms } => (from_ms, to_ms),
+        };
+        self.samples
+            .iter()
+            .filter(|s| s.ts_ms >= from && s.ts_ms <= to)
+            .cloned()
+            .collect()
+    }
+
+    /// from_ms..=to_ms の間のサンプル（古い順）
+    pub fn samples_between(&self, from_ms: u64, to_ms: u64) -> impl Iterator<Item = &Sample> {
+        self.samples.iter().filter(move |s| s.ts_ms >= from_ms && s.ts_ms <= to_ms)
+    }
+
+    /// ts_ms 以前 secs 秒の間で、値の取れている (ts_ms, 値) だけを古い順に返す
+    pub fn series(&self, ts_ms: u64, secs: u64, value: impl Fn(&MetricsSnapshot) -> Option<f64>) -> Vec<(u64, f64)> {
+        self.samples_between(ts_ms.saturating_sub(secs.saturating_mul(1000)), ts_ms)
+            .filter_map(|s| value(&s.snapshot).map(|v| (s.ts_ms, v)))
+            .collect()
+    }
+}
+
+pub type SharedHistory = Arc<RwLock<History>>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(ts_ms: u64, cpu: Option<f32>) -> Sample {
+        Sample { ts_ms, snapshot: MetricsSnapshot { cpu_pct: cpu, ..MetricsSnapshot::default() } }
+    }
+
+    fn filled(capacity: usize, count: u64) -> History {
+        let mut h = History::new(capacity);
+        for i in 0..count {
+            h.push(sample(i * 1000, Some(i as f32)));
+        }
+        h
+    }
+
+    #[test]
+    fn drops_the_oldest_sample_when_full() {
+        let h = filled(3, 5);
+        let ts: Vec<u64> = h.samples_between(0, u64::MAX).map(|s| s.ts_ms).collect();
+        assert_eq!(ts, [2000, 3000, 4000]);
+    }
+
+    #[test]
+    fn between_is_inclusive_on_both_ends() {
+        let h = filled(10, 10);
+        let ts: Vec<u64> = h.range(HistoryRange::Between { from_ms: 2000, to_ms: 4000 }).iter().map(|s| s.ts_ms).collect();
+        assert_eq!(ts, [2000, 3000, 4000]);
+        assert!(h.range(HistoryRange::Between { from_ms: 4500, to_ms: 4900 }).is_empty());
+    }
+
+    #[test]
+    fn last_counts_back_from_now() {
+        let mut h = History::new(10);
+        let now = now_ms();
+        h.push(sample(now - 120_000, None));
+        h.push(sample(now - 30_000, None));
+        h.push(sample(now, None));
+        assert_eq!(h.range(HistoryRange::Last(60)).len(), 2);
+        assert_eq!(h.range(HistoryRange::Last(600)).len(), 3);
+        // 大きすぎる指定は「全部」
+        assert_eq!(h.range(HistoryRange::Last(u64::MAX)).len(), 3);
+    }
+
+    #[test]
+    fn series_skips_missing_values() {
+        let mut h = filled(10, 5);
+        h.push(sample(5000, None));
+        h.push(sample(6000, Some(6.0)));
+        let series = h.series(6000, 3, |s| s.cpu_pct.map(f64::from));
+        assert_eq!(series, [(3000, 3.0), (4000, 4.0), (6000, 6.0)]);
+    }
+}
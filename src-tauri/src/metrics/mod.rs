@@ -1,3 +1,5 @@
 pub mod types;
 pub mod collect_macos;
-pub mod service;
\ No newline at end of file
+pub mod service;
+pub mod history;
+pub mod process;
\ No newline at end of file
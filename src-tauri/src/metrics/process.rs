@@ -0,0 +1,135 @@
+// 見張るプロセス（設定の processes）の CPU% と RSS
+//
+// - 名前か正規表現に一致したプロセスをまとめて1項目にする（node が何個あっても合計）
+// - CPU% は前回の取得からの差分で決まるので、System は取得のたびに作り直さない
+use regex::Regex;
+use sysinfo::{ProcessRefreshKind, System};
+
+use crate::config::schema::ProcessWatch;
+use crate::metrics::types::ProcessSample;
+
+/// 設定から作った照合器。設定が変わったときだけ作り直す
+#[derive(Debug, Default)]
+pub struct Watchlist {
+    source: Vec<ProcessWatch>,
+    // pattern が空か不正なら None（name との完全一致で探す）
+    patterns: Vec<Option<Regex>>,
+}
+
+impl Watchlist {
+    pub fn compile(watches: &[ProcessWatch]) -> Self {
+        let patterns = watches
+            .iter()
+            .map(|w| if w.pattern.is_empty() { None } else { Regex::new(&w.pattern).ok() })
+            .collect();
+        Self { source: watches.to_vec(), patterns }
+    }
+
+    pub fn is_compiled_from(&self, watches: &[ProcessWatch]) -> bool {
+        self.source == watches
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.source.is_empty()
+    }
+
+    fn matches(&self, i: usize, process_name: &str) -> bool {
+        match &self.patterns[i] {
+            Some(re) => re.is_match(process_name),
+            None => self.source[i].name == process_name,
+        }
+    }
+
+    /// (プロセス名, CPU%, RSS バイト) の一覧を見張る項目ごとに合計する
+    pub fn aggregate<'a>(&self, processes: impl IntoIterator<Item = (&'a str, f32, u64)>) -> Vec<ProcessSample> {
+        let mut out: Vec<ProcessSample> = self
+            .source
+            .iter()
+            .map(|w| ProcessSample { name: w.name.clone(), ..ProcessSample::default() })
+            .collect();
+        for (name, cpu, rss) in processes {
+            for (i, sample) in out.iter_mut().enumerate() {
+                if !self.matches(i, name) {
+                    continue;
+                }
+                sample.count += 1;
+                sample.cpu_pct = Some(sample.cpu_pct.unwrap_or(0.0) + cpu);
+                sample.rss_bytes = Some(sample.rss_bytes.unwrap_or(0) + rss);
+            }
+        }
+        out
+    }
+}
+
+/// 取得用のタスクが1つ持つ
+#[derive(Default)]
+pub struct ProcessSampler {
+    sys: System,
+    watchlist: Watchlist,
+}
+
+impl ProcessSampler {
+    pub fn sample(&mut self, watches: &[ProcessWatch]) -> Vec<ProcessSample> {
+        if !self.watchlist.is_compiled_from(watches) {
+            self.watchlist = Watchlist::compile(watches);
+        }
+        if self.watchlist.is_empty() {
+            return Vec::new();
+        }
+        self.sys.refresh_processes_specifics(ProcessRefreshKind::new().with_cpu().with_memory());
+        let processes = self.sys.processes().values().map(|p| (p.name(), p.cpu_usage(), p.memory()));
+        self.watchlist.aggregate(processes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn watch(name: &str, pattern: &str) -> ProcessWatch {
+        ProcessWatch { name: name.to_string(), pattern: pattern.to_string(), ..ProcessWatch::default() }
+    }
+
+    #[test]
+    fn sums_matching_processes_per_watch() {
+        let list = Watchlist::compile(&[watch("node", ""), watch("docker", "^(com\\.)?docker"), watch("ra", "rust-analyzer")]);
+        let running = [
+            ("node", 10.0, 100),
+            ("node", 5.5, 50),
+            ("nodemon", 1.0, 1),
+            ("com.docker.backend", 20.0, 2000),
+            ("docker", 1.0, 10),
+        ];
+        let samples = list.aggregate(running);
+
+        assert_eq!(samples[0], ProcessSample { name: "node".to_string(), count: 2, cpu_pct: Some(15.5), rss_bytes: Some(150) });
+        assert_eq!((samples[1].count, samples[1].rss_bytes), (2, Some(2010)));
+        // 動いていなければ値は無し
+        assert_eq!(samples[2], ProcessSample { name: "ra".to_string(), ..ProcessSample::default() });
+    }
+
+    #[test]
+    fn classifies_rss_in_gigabytes() {
+        use crate::ui::types::{AlertLevel, ProcessMetric};
+
+        let ra = watch("rust-analyzer", "");
+        let sample = ProcessSample { name: ra.name.clone(), count: 1, cpu_pct: Some(30.0), rss_bytes: Some(5 << 30) };
+        let p = ProcessMetric::classify(&sample, &ra);
+        assert_eq!(p.rss_gb.unwrap().level, AlertLevel::Critical);
+        assert_eq!(p.cpu.unwrap().level, AlertLevel::Safe);
+        assert_eq!(p.level(), Some(AlertLevel::Critical));
+        assert_eq!(p.rss_key(), "proc:rust-analyzer:rss");
+
+        let stopped = ProcessMetric::classify(&ProcessSample { name: ra.name.clone(), ..ProcessSample::default() }, &ra);
+        assert_eq!(stopped.level(), None);
+    }
+
+    #[test]
+    fn recompiles_only_when_the_watchlist_changes() {
+        let watches = vec![watch("node", "")];
+        let list = Watchlist::compile(&watches);
+        assert!(list.is_compiled_from(&watches));
+        assert!(!list.is_compiled_from(&[watch("node", "^node")]));
+        assert!(Watchlist::default().is_empty());
+    }
+}
@@ -1,22 +1,86 @@
+use std::collections::VecDeque;
 use std::sync::Arc;
-use tokio::sync::RwLock;
-use std::time::{Duration, Instant};
+use std::time::Duration;
+use tauri::Manager;
+use tokio::sync::watch;
+use tokio::task::JoinSet;
 
-use crate::metrics::types::{SharedMetrics, MetricsSnapshot};
+use crate::config::schema::{ProcessWatch, SamplingConfig};
+use crate::metrics::types::{ProbeResult, SharedMetrics};
+use crate::alerts::anomaly::{SharedBaseline, BASELINE_FILE};
+use crate::alerts::evaluate::Evaluator;
+use crate::ipc::MetricsUpdate;
+use crate::metrics::history::{Sample, SharedHistory};
+use crate::metrics::process::ProcessSampler;
+use crate::ui::types::{TrayConfig, TrayUiState};
 
-pub fn spawn_metric_tasks(metrics: SharedMetrics) {
-    let get_cpu_interval = 1;
-    let get_mem_interval = 1;
-    let get_nw_interval = 3;
-    let get_nw_timeout = 2;
+/// 取得間隔・計測先・見張るプロセスの変更をタスクに伝える
+pub struct SamplingControl {
+    sampling: watch::Sender<SamplingConfig>,
+    processes: watch::Sender<Vec<ProcessWatch>>,
+}
+
+impl SamplingControl {
+    pub fn new(cfg: &TrayConfig) -> Self {
+        Self {
+            sampling: watch::channel(cfg.sampling.clone()).0,
+            processes: watch::channel(cfg.processes.clone()).0,
+        }
+    }
+
+    pub fn subscribe(&self) -> watch::Receiver<SamplingConfig> {
+        self.sampling.subscribe()
+    }
+
+    pub fn subscribe_processes(&self) -> watch::Receiver<Vec<ProcessWatch>> {
+        self.processes.subscribe()
+    }
+
+    pub fn update(&self, cfg: &TrayConfig) {
+        send_if_changed(&self.sampling, &cfg.sampling);
+        send_if_changed(&self.processes, &cfg.processes);
+    }
+}
+
+fn send_if_changed<T: Clone + PartialEq>(tx: &watch::Sender<T>, next: &T) {
+    tx.send_if_modified(|cur| {
+        if cur == next {
+            return false;
+        }
+        *cur = next.clone();
+        true
+    });
+}
+
+const DISK_INTERVAL_SECS: u64 = 30;
+// プロセスの一覧を取るのは重めなので、CPU/Mem より間隔を空ける
+const PROC_INTERVAL_SECS: u64 = 3;
+
+fn interval_secs(secs: u64) -> tokio::time::Interval {
+    tokio::time::interval(Duration::from_secs(secs.max(1)))
+}
+
+pub fn spawn_metric_tasks(
+    metrics: SharedMetrics,
+    sampling: watch::Receiver<SamplingConfig>,
+    mut processes: watch::Receiver<Vec<ProcessWatch>>,
+) {
+    let nw_p95_window = 20;
 
     // CPU
     tauri::async_runtime::spawn({
         let metrics = Arc::clone(&metrics);
+        let mut sampling = sampling.clone();
         async move {
-            let mut tick = tokio::time::interval(Duration::from_secs(get_cpu_interval));
+            let mut tick = interval_secs(sampling.borrow().cpu_secs);
             loop {
-                tick.tick().await;
+                tokio::select! {
+                    _ = tick.tick() => {}
+                    Ok(()) = sampling.changed() => {
+                        tick = interval_secs(sampling.borrow().cpu_secs);
+                        continue;
+                    }
+                }
                 let v = crate::mac_metrics::read_cpu_usage_pct().await.ok();
                 let mut m = metrics.write().await;
                 m.cpu_pct = v;
@@ -27,36 +91,166 @@ pub fn spawn_metric_tasks(metrics: SharedMetrics) {
     // MEM
     tauri::async_runtime::spawn({
         let metrics = Arc::clone(&metrics);
+        let mut sampling = sampling.clone();
         async move {
-            let mut tick = tokio::time::interval(Duration::from_secs(get_mem_interval));
+            let mut tick = interval_secs(sampling.borrow().mem_secs);
             loop {
-                tick.tick().await;
-                let v  = crate::mac_metrics::read_memory_pressure_pct().await.ok().map(|x| x as f32);;
+                tokio::select! {
+                    _ = tick.tick() => {}
+                    Ok(()) = sampling.changed() => {
+                        tick = interval_secs(sampling.borrow().mem_secs);
+                        continue;
+                    }
+                }
+                let v  = crate::mac_metrics::read_memory_pressure_pct().await.ok().map(|x| x as f32);
                 let mut m = metrics.write().await;
                 m.mem_pressure_pct = v;
             }
         }
     });
 
-    // NW（timeoutあり・1回だけ）
+    // DISK（増え方がゆっくりなので間隔は固定）
     tauri::async_runtime::spawn({
         let metrics = Arc::clone(&metrics);
         async move {
-            let mut tick = tokio::time::interval(Duration::from_secs(get_nw_interval));
+            let mut tick = interval_secs(DISK_INTERVAL_SECS);
             loop {
                 tick.tick().await;
+                let v = crate::mac_metrics::read_disk_used_pct().await.ok();
+                let mut m = metrics.write().await;
+                m.disk_used_pct = v;
+            }
+        }
+    });
 
-                let v = tokio::time::timeout(
-                    Duration::from_secs(get_nw_timeout),
-                    crate::mac_metrics::network_latency_ms(),
-                )
+    // PROC（一覧が変わったらすぐ取り直す）
+    tauri::async_runtime::spawn({
+        let metrics = Arc::clone(&metrics);
+        async move {
+            let mut tick = interval_secs(PROC_INTERVAL_SECS);
+            let mut sampler = ProcessSampler::default();
+            loop {
+                tokio::select! {
+                    _ = tick.tick() => {}
+                    Ok(()) = processes.changed() => {}
+                }
+                let watches = processes.borrow_and_update().clone();
+                // プロセス一覧の取得は重いので、ワーカーを塞がないよう sampler ごと渡して返してもらう
+                let result = tauri::async_runtime::spawn_blocking(move || {
+                    let v = sampler.sample(&watches);
+                    (sampler, v)
+                })
+                .await;
+                let Ok((returned, v)) = result else { break; };
+                sampler = returned;
+                let mut m = metrics.write().await;
+                m.procs = v;
+            }
+        }
+    });
+
+    // NW（計測先ごとに timeout あり・1回だけ）
+    tauri::async_runtime::spawn({
+        let metrics = Arc::clone(&metrics);
+        let mut sampling = sampling.clone();
+        async move {
+            let mut tick = interval_secs(sampling.borrow().nw_secs);
+            let mut recent: VecDeque<f64> = VecDeque::with_capacity(nw_p95_window);
+            loop {
+                tokio::select! {
+                    _ = tick.tick() => {}
+                    Ok(()) = sampling.changed() => {
+                        tick = interval_secs(sampling.borrow().nw_secs);
+                        recent.clear();
+                        continue;
+                    }
+                }
+
+                let cfg = sampling.borrow().clone();
+                let probes = probe_all(&cfg.nw_targets, Duration::from_secs(cfg.nw_timeout_secs)).await;
+                // 先頭から順に、応答のあった計測先を代表値にする
+                let v = probes.iter().find_map(|p| p.ms);
+
+                if let Some(ms) = v {
+                    if recent.len() == nw_p95_window {
+                        recent.pop_front();
+                    }
+                    recent.push_back(ms);
+                }
+
+                let mut m = metrics.write().await;
+                m.nw_ms = v;
+                m.nw_p95_ms = percentile(&recent, 95.0);
+                m.nw_probes = probes;
+            }
+        }
+    });
+}
+
+// 全計測先に並行して ping し、設定の順番で返す
+async fn probe_all(targets: &[String], timeout: Duration) -> Vec<ProbeResult> {
+    let mut set = JoinSet::new();
+    for (i, target) in targets.iter().enumerate() {
+        let target = target.clone();
+        set.spawn(async move {
+            let ms = tokio::time::timeout(timeout, crate::mac_metrics::network_latency_ms(&target))
                 .await
                 .ok()
                 .and_then(|r| r.ok());
+            (i, ProbeResult { target, ms })
+        });
+    }
 
-                let mut m = metrics.write().await;
-                m.nw_ms = v;
+    let mut results: Vec<(usize, ProbeResult)> = Vec::with_capacity(targets.len());
+    while let Some(joined) = set.join_next().await {
+        if let Ok(r) = joined {
+            results.push(r);
+        }
+    }
+    results.sort_by_key(|(i, _)| *i);
+    results.into_iter().map(|(_, r)| r).collect()
+}
+
+/// 1秒ごとに現在のスナップショットを履歴に積み、レベルを付けて webview などに配る
+pub fn spawn_sampler(app: tauri::AppHandle, metrics: SharedMetrics, history: SharedHistory) {
+    let sample_interval = 1;
+
+    tauri::async_runtime::spawn(async move {
+        let mut tick = tokio::time::interval(Duration::from_secs(sample_interval));
+        let baseline_path = app.path().app_data_dir().ok().map(|dir| dir.join(BASELINE_FILE));
+        let baseline = SharedBaseline::load(baseline_path);
+        // 終了するときにも保存する
+        app.manage(baseline.clone());
+        let mut evaluator = Evaluator::with_baseline(baseline);
+        loop {
+            tick.tick().await;
+            let sample = Sample::now(metrics.read().await.clone());
+            let cfg = match app.try_state::<TrayUiState>() {
+                Some(st) => st.config.lock().unwrap().clone(),
+                None => TrayConfig::default(),
+            };
+            let classified = {
+                let mut h = history.write().await;
+                h.push(sample.clone());
+                // 評価している間も get_history や CSV の書き出しは読めるように
+                let h = h.downgrade();
+                evaluator.evaluate(&cfg, &h, &sample)
+            };
+            if let Some(baseline) = evaluator.baseline_to_save(sample.ts_ms) {
+                tauri::async_runtime::spawn_blocking(move || baseline.save_blocking());
             }
+            crate::ipc::emit_update(&app, MetricsUpdate::new(sample, classified));
         }
     });
-}
\ No newline at end of file
+}
+
+// nearest-rank 法でのパーセンタイル
+fn percentile(samples: &VecDeque<f64>, p: f64) -> Option<f64> {
+    if samples.is_empty() {
+        return None;
+    }
+    let mut sorted: Vec<f64> = samples.iter().copied().collect();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    Some(sorted[rank.clamp(1, sorted.len()) - 1])
+}
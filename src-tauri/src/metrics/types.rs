@@ -4,9 +4,10 @@
 /// MetricsSnapshot
 use std::fmt;
 use std::sync::Arc;
+use serde::{Deserialize, Serialize};
 use tokio::sync::RwLock;
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Percent(pub f32);
 impl Percent {
     pub fn clamp_0_100(self) -> Self {
@@ -20,7 +21,7 @@ impl fmt::Display for Percent {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Millisecond(pub f32);
 
 impl fmt::Display for Millisecond {
@@ -29,11 +30,35 @@ impl fmt::Display for Millisecond {
     }
 }
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct MetricsSnapshot {
     pub cpu_pct: Option<f32>,
     pub mem_pressure_pct: Option<f32>,
     pub nw_ms: Option<f64>,
+    // 直近の NW 計測値の p95
+    pub nw_p95_ms: Option<f64>,
+    // 計測先ごとの結果（nw_ms は先頭から見て最初に応答があったもの）
+    pub nw_probes: Vec<ProbeResult>,
+    // 起動ボリュームの使用率（枯渇予測にだけ使う）
+    pub disk_used_pct: Option<f32>,
+    // 見張っているプロセス（設定の processes と同じ順）
+    pub procs: Vec<ProcessSample>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ProbeResult {
+    pub target: String,
+    pub ms: Option<f64>,
+}
+
+/// 名前か正規表現に一致したプロセスの合計
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProcessSample {
+    pub name: String,
+    // 一致したプロセスの数。0 なら値は None
+    pub count: usize,
+    pub cpu_pct: Option<f32>,
+    pub rss_bytes: Option<u64>,
 }
 
 pub type SharedMetrics = Arc<RwLock<MetricsSnapshot>>;
@@ -0,0 +1,39 @@
+// osascript で名前の入力やファイル選択を受け付ける（キャンセルされたら None）
+use std::path::PathBuf;
+use tokio::process::Command;
+
+use super::notify::escape;
+
+pub async fn ask_text(prompt: &str, default_answer: &str) -> Option<String> {
+    let script = format!(
+        "text returned of (display dialog \"{}\" default answer \"{}\" with title \"Vital Monitor\")",
+        escape(prompt),
+        escape(default_answer),
+    );
+    run(&script).await
+}
+
+pub async fn choose_file(prompt: &str) -> Option<PathBuf> {
+    let script = format!("POSIX path of (choose file with prompt \"{}\")", escape(prompt));
+    run(&script).await.map(PathBuf::from)
+}
+
+/// 保存先を選ばせる（同名ファイルがあれば上書きしてよいかは Finder 側で確認される）
+pub async fn choose_save_path(prompt: &str, default_name: &str) -> Option<PathBuf> {
+    let script = format!(
+        "POSIX path of (choose file name with prompt \"{}\" default name \"{}\")",
+        escape(prompt),
+        escape(default_name),
+    );
+    run(&script).await.map(PathBuf::from)
+}
+
+async fn run(script: &str) -> Option<String> {
+    let output = Command::new("osascript").arg("-e").arg(script).output().await.ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let text = String::from_utf8_lossy(&output.stdout);
+    // 出力の最後の改行だけ落とす
+    Some(text.strip_suffix('\n').unwrap_or(&text).to_string())
+}
@@ -1,2 +1,5 @@
 pub mod types;
-pub mod tray;
\ No newline at end of file
+pub mod tray;
+pub mod settings;
+pub mod notify;
+pub mod dialog;
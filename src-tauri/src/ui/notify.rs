@@ -0,0 +1,18 @@
+// macOS の通知センターに出す（osascript 経由）
+use tokio::process::Command;
+
+pub fn notify(title: &str, body: &str) {
+    let script = format!(
+        "display notification \"{}\" with title \"{}\"",
+        escape(body),
+        escape(title),
+    );
+    tauri::async_runtime::spawn(async move {
+        let _ = Command::new("osascript").arg("-e").arg(script).output().await;
+    });
+}
+
+// AppleScript の文字列リテラル用
+pub(super) fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
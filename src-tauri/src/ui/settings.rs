@@ -0,0 +1,21 @@
+// 設定ウィンドウ（中身は webview 側の #settings 画面）
+use tauri::{Manager, WebviewUrl, WebviewWindowBuilder};
+
+pub const SETTINGS_LABEL: &str = "settings";
+
+pub fn open(app: &tauri::AppHandle) {
+    if let Some(window) = app.get_webview_window(SETTINGS_LABEL) {
+        let _ = window.show();
+        let _ = window.set_focus();
+        return;
+    }
+
+    let built = WebviewWindowBuilder::new(app, SETTINGS_LABEL, WebviewUrl::App("index.html#settings".into()))
+        .title("Vital Monitor Settings")
+        .inner_size(480.0, 640.0)
+        .resizable(true)
+        .build();
+    if let Err(e) = built {
+        eprintln!("failed to open settings window: {}", e);
+    }
+}
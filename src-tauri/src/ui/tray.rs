@@ -1,12 +1,21 @@
 use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use tauri::{
     App,
     Manager,
+    PhysicalPosition,
+    Rect,
+    WindowEvent,
+    Wry,
     menu::{Menu, MenuItem, PredefinedMenuItem, Submenu, CheckMenuItem},
-    tray::TrayIconBuilder,
+    tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
 };
-use tokio::fs;
-use crate::ui::{self, types::{DisplayMode, TrayConfig, TrayUiState}};
+use crate::ui::{self, types::{Density, DisplayMode, IconSet, IconTheme, TrayConfig, TrayUiState}};
+use crate::alerts::log::AlertLog;
+use crate::alerts::quiet::{self, DoNotDisturb};
+use crate::config::profiles::{self, ProfileState};
+use crate::config::writer::ConfigWriter;
+use crate::metrics::history::now_ms;
 use crate::TrayState;
 
 pub fn build_tray(app: &App, initial_cfg: TrayConfig) -> tauri::Result<()> {
@@ -31,11 +40,44 @@ pub fn build_tray(app: &App, initial_cfg: TrayConfig) -> tauri::Result<()> {
 
     let mi_mode_list = CheckMenuItem::with_id(app, "mode_list", "List", true, true, None::<&str>)?;
     let mi_mode_rotation = CheckMenuItem::with_id(app, "mode_rotation", "Rotation", true, false, None::<&str>)?;
-    let mode_items: [&dyn tauri::menu::IsMenuItem<_>; 2] = [&mi_mode_list, &mi_mode_rotation];
+    let mi_mode_summary = CheckMenuItem::with_id(app, "mode_summary", "Summary", true, false, None::<&str>)?;
+    let mi_pin_critical = CheckMenuItem::with_id(app, "toggle_pin_critical", "Pin Critical", true, false, None::<&str>)?;
+    let mi_density_ultra = CheckMenuItem::with_id(app, "density_ultra", "Ultra Compact", true, false, None::<&str>)?;
+    let mi_density_compact = CheckMenuItem::with_id(app, "density_compact", "Compact", true, false, None::<&str>)?;
+    let mi_density_verbose = CheckMenuItem::with_id(app, "density_verbose", "Verbose", true, true, None::<&str>)?;
+    let density_items: [&dyn tauri::menu::IsMenuItem<_>; 3] = [
+        &mi_density_ultra,
+        &mi_density_compact,
+        &mi_density_verbose,
+    ];
+    let density_sub = Submenu::with_items(app, "Density", true, &density_items)?;
+    let mode_items: [&dyn tauri::menu::IsMenuItem<_>; 7] = [
+        &mi_mode_list,
+        &mi_mode_rotation,
+        &mi_mode_summary,
+        &PredefinedMenuItem::separator(app)?,
+        &mi_pin_critical,
+        &PredefinedMenuItem::separator(app)?,
+        &density_sub,
+    ];
     let mode_sub  = Submenu::with_items(app, "Display Mode", true, &mode_items)?;
 
     let mi_is_alert = CheckMenuItem::with_id(app, "toggle_alert", "Alert Icon", true, true, None::<&str>)?;
-    let alert_items: [&dyn tauri::menu::IsMenuItem<_>; 1] = [&mi_is_alert];
+    let mi_theme_emoji = CheckMenuItem::with_id(app, "theme_emoji", "Emoji", true, true, None::<&str>)?;
+    let mi_theme_ascii = CheckMenuItem::with_id(app, "theme_ascii", "ASCII", true, false, None::<&str>)?;
+    let mi_theme_bars = CheckMenuItem::with_id(app, "theme_bars", "Bars", true, false, None::<&str>)?;
+    let mi_theme_colorblind = CheckMenuItem::with_id(app, "theme_colorblind", "Colorblind Safe", true, false, None::<&str>)?;
+    let mi_theme_custom = CheckMenuItem::with_id(app, "theme_custom", "Custom…", true, false, None::<&str>)?;
+    let theme_items: [&dyn tauri::menu::IsMenuItem<_>; 6] = [
+        &mi_theme_emoji,
+        &mi_theme_ascii,
+        &mi_theme_bars,
+        &mi_theme_colorblind,
+        &PredefinedMenuItem::separator(app)?,
+        &mi_theme_custom,
+    ];
+    let theme_sub = Submenu::with_items(app, "Icon Theme", true, &theme_items)?;
+    let alert_items: [&dyn tauri::menu::IsMenuItem<_>; 2] = [&mi_is_alert, &theme_sub];
     let alert_sub = Submenu::with_items(app, "Alert", true, &alert_items)?;
 
     let options_items: [&dyn tauri::menu::IsMenuItem<_>; 3] = [
@@ -44,18 +86,21 @@ pub fn build_tray(app: &App, initial_cfg: TrayConfig) -> tauri::Result<()> {
         &alert_sub,
     ];
     let options_sub = Submenu::with_items(app, "Options", true, &options_items)?;
+    // 中身は保存済みのプロファイルに合わせて作り直す
+    let profiles_sub = Submenu::new(app, "Profiles", true)?;
+    fill_profiles_menu(app.handle(), &profiles_sub)?;
+    let alerts_sub = Submenu::new(app, "Recent Alerts", true)?;
+    fill_alerts_menu(app.handle(), &alerts_sub)?;
+    let dnd_sub = Submenu::new(app, "Do Not Disturb", true)?;
+    fill_dnd_menu(app.handle(), &dnd_sub)?;
+    let export_i = MenuItem::with_id(app, "export_history", "Export Last N Minutes…", true, None::<&str>)?;
+    let settings_i = MenuItem::with_id(app, "settings", "Settings…", true, None::<&str>)?;
     let exit_i = MenuItem::with_id(app, "exit", "Exit", true, None::<&str>)?;
     // ルートメニュー
-    let menu = Menu::with_items(app, &[&options_sub, &PredefinedMenuItem::separator(app)?, &exit_i])?;
+    let menu = Menu::with_items(app, &[&options_sub, &profiles_sub, &alerts_sub, &dnd_sub, &export_i, &settings_i, &PredefinedMenuItem::separator(app)?, &exit_i])?;
 
     let ui_state = TrayUiState {
-        config: Mutex::new(TrayConfig {
-        show_cpu: initial_cfg.show_cpu,
-        show_mem: initial_cfg.show_mem,
-        show_nw: initial_cfg.show_nw,
-        mode: initial_cfg.mode,
-        is_alert: initial_cfg.is_alert,
-        }),
+        config: Mutex::new(initial_cfg),
         mi_show_cpu,
         mi_show_mem,
         mi_show_nw,
@@ -65,19 +110,113 @@ pub fn build_tray(app: &App, initial_cfg: TrayConfig) -> tauri::Result<()> {
         mi_show_all,
         mi_mode_list,
         mi_mode_rotation,
+        mi_mode_summary,
+        mi_pin_critical,
         mi_is_alert,
+        mi_theme_emoji,
+        mi_theme_ascii,
+        mi_theme_bars,
+        mi_theme_colorblind,
+        mi_theme_custom,
+        mi_density_ultra,
+        mi_density_compact,
+        mi_density_verbose,
     };
     // 初期チェック同期
     ui_state.sync_menu_checks();
     app.manage(ui_state);
+    app.manage(ProfilesMenu(profiles_sub));
+    app.manage(AlertsMenu(alerts_sub));
+    app.manage(DndMenu(dnd_sub));
 
     let tray = TrayIconBuilder::with_id("tray-1")
         .menu(&menu)
-        .show_menu_on_left_click(true)
+        // 左クリックはポップオーバー、右クリックはメニュー
+        .show_menu_on_left_click(false)
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                rect,
+                ..
+            } = event
+            {
+                toggle_popover(tray.app_handle(), rect);
+            }
+        })
         .on_menu_event(|app, event| {
-            if event.id.as_ref() == "exit" {
-                app.exit(0);
-                return;
+            match event.id.as_ref() {
+                "exit" => {
+                    // debounce 中の変更を落とさないように書いてから終了する
+                    let cfg = app.state::<TrayUiState>().config.lock().unwrap().clone();
+                    app.state::<ConfigWriter>().flush_blocking(&cfg);
+                    crate::export::local_api::remove_socket(app, &cfg.export.local_api);
+                    if let Some(baseline) = app.try_state::<crate::alerts::anomaly::SharedBaseline>() {
+                        baseline.save_blocking();
+                    }
+                    app.exit(0);
+                    return;
+                }
+                "settings" => { ui::settings::open(app); return; }
+                "theme_custom" => {
+                    tauri::async_runtime::spawn(custom_icons_dialog(app.clone()));
+                    return;
+                }
+                "export_history" => {
+                    tauri::async_runtime::spawn(export_history_dialog(app.clone()));
+                    return;
+                }
+                "profile_save" => {
+                    tauri::async_runtime::spawn(save_profile_dialog(app.clone()));
+                    return;
+                }
+                "profile_auto" => {
+                    profiles::update_state(app, |file| file.auto_switch = !file.auto_switch);
+                    return;
+                }
+                "profile_export" => {
+                    tauri::async_runtime::spawn(export_profile_dialog(app.clone()));
+                    return;
+                }
+                "profile_import" => {
+                    tauri::async_runtime::spawn(import_profile_dialog(app.clone()));
+                    return;
+                }
+                "alert_ack_all" => {
+                    app.state::<Mutex<AlertLog>>().lock().unwrap().acknowledge_all();
+                    refresh_alerts_menu(app);
+                    return;
+                }
+                "alert_clear" => {
+                    app.state::<Mutex<AlertLog>>().lock().unwrap().clear();
+                    refresh_alerts_menu(app);
+                    return;
+                }
+                "dnd_30m" | "dnd_1h" | "dnd_tomorrow" | "dnd_off" => {
+                    let until = match event.id.as_ref() {
+                        "dnd_30m" => Some(now_ms() + 30 * 60 * 1000),
+                        "dnd_1h" => Some(now_ms() + 60 * 60 * 1000),
+                        "dnd_tomorrow" => Some(quiet::tomorrow_ms()),
+                        _ => None,
+                    };
+                    app.state::<DoNotDisturb>().set(until);
+                    refresh_dnd_menu(app);
+                    return;
+                }
+                id if id.starts_with(ALERT_ACK_ID_PREFIX) => {
+                    app.state::<Mutex<AlertLog>>().lock().unwrap().acknowledge(&id[ALERT_ACK_ID_PREFIX.len()..]);
+                    refresh_alerts_menu(app);
+                    return;
+                }
+                id if id.starts_with(PROFILE_ID_PREFIX) => {
+                    if let Err(e) = profiles::switch(app, &id[PROFILE_ID_PREFIX.len()..]) {
+                        report_profile_error(&e);
+                        // クリックで付いたチェックを元に戻す
+                        refresh_profiles_menu(app);
+                    }
+                    return;
+                }
+                _ => {}
             }
 
             let ui_state = app.state::<TrayUiState>();
@@ -97,16 +236,25 @@ pub fn build_tray(app: &App, initial_cfg: TrayConfig) -> tauri::Result<()> {
                     "show_all" => { cfg.show_cpu = true; cfg.show_mem = true; cfg.show_nw = true; }
                     "mode_list" => { cfg.mode = DisplayMode::List; }
                     "mode_rotation" => { cfg.mode = DisplayMode::Rotation; }
+                    "mode_summary" => { cfg.mode = DisplayMode::Summary; }
+                    "toggle_pin_critical" => { cfg.pin_critical = !cfg.pin_critical; }
                     "toggle_alert" => { cfg.is_alert = !cfg.is_alert; }
+                    "density_ultra" => { cfg.density = Density::UltraCompact; }
+                    "density_compact" => { cfg.density = Density::Compact; }
+                    "density_verbose" => { cfg.density = Density::Verbose; }
+                    "theme_emoji" => { cfg.icon_theme = IconTheme::Emoji; }
+                    "theme_ascii" => { cfg.icon_theme = IconTheme::Ascii; }
+                    "theme_bars" => { cfg.icon_theme = IconTheme::Bars; }
+                    "theme_colorblind" => { cfg.icon_theme = IconTheme::ColorblindSafe; }
                     _ => {}
                 }
 
-                *cfg // ← ロック中にコピーして返す（ここでロック解放される）
+                cfg.clone() // ← ロック中にコピーして返す（ここでロック解放される）
             };
 
             // （ロック外） UI反映と保存
             ui_state.sync_menu_checks();
-            save_config_async(app.app_handle().clone(), cfg_copy);
+            crate::config::store::save(app, cfg_copy);
         })
         .title("Vital Monitor")
         .build(app)?;
@@ -114,17 +262,297 @@ pub fn build_tray(app: &App, initial_cfg: TrayConfig) -> tauri::Result<()> {
     app.manage(TrayState {
         tray: Mutex::new(tray),
     });
+    refresh_profiles_menu(app.handle());
+    setup_popover(app);
+    Ok(())
+}
+
+const PROFILE_ID_PREFIX: &str = "profile:";
+
+pub struct ProfilesMenu(Submenu<Wry>);
+
+/// プロファイルの追加や切り替えのあとに呼ぶ
+pub fn refresh_profiles_menu(app: &tauri::AppHandle) {
+    let Some(menu) = app.try_state::<ProfilesMenu>() else { return; };
+    if let Err(e) = fill_profiles_menu(app, &menu.0) {
+        eprintln!("failed to rebuild profiles menu: {}", e);
+    }
+}
+
+fn fill_profiles_menu(app: &tauri::AppHandle, sub: &Submenu<Wry>) -> tauri::Result<()> {
+    for item in sub.items()? {
+        sub.remove(&item)?;
+    }
+
+    let profiles = app.state::<ProfileState>();
+    let active = profiles.active();
+    let auto_switch = profiles.file.lock().unwrap().auto_switch;
+    let names = profiles.names();
+
+    // 今どのプロファイルか分かるようにメニュー名とツールチップに出す
+    sub.set_text(match &active {
+        Some(name) => format!("Profile: {}", name),
+        None => "Profiles".to_string(),
+    })?;
+    if let Some(tray) = app.try_state::<TrayState>() {
+        let tooltip = match &active {
+            Some(name) => format!("Vital Monitor ({})", name),
+            None => "Vital Monitor".to_string(),
+        };
+        tray.tray.lock().unwrap().set_tooltip(Some(tooltip))?;
+    }
+
+    if names.is_empty() {
+        sub.append(&MenuItem::with_id(app, "profile_none", "No Saved Profiles", false, None::<&str>)?)?;
+    }
+    for name in names {
+        let checked = active.as_deref() == Some(name.as_str());
+        let id = format!("{}{}", PROFILE_ID_PREFIX, name);
+        sub.append(&CheckMenuItem::with_id(app, id, &name, true, checked, None::<&str>)?)?;
+    }
+
+    sub.append(&PredefinedMenuItem::separator(app)?)?;
+    sub.append(&CheckMenuItem::with_id(app, "profile_auto", "Switch Automatically", true, auto_switch, None::<&str>)?)?;
+    sub.append(&MenuItem::with_id(app, "profile_save", "Save Current as Profile…", true, None::<&str>)?)?;
+    sub.append(&MenuItem::with_id(app, "profile_export", "Export Profile…", true, None::<&str>)?)?;
+    sub.append(&MenuItem::with_id(app, "profile_import", "Import Profile…", true, None::<&str>)?)?;
     Ok(())
 }
 
-fn save_config_async(app: tauri::AppHandle, cfg: TrayConfig) {
-    tauri::async_runtime::spawn(async move {
-        let Ok(dir) = app.path().app_config_dir() else { return; };
-        let path = dir.join("tray_config.json");
+const ALERT_ACK_ID_PREFIX: &str = "alert_ack:";
+// メニューに並べる遷移の件数
+const RECENT_ALERTS: usize = 10;
+
+pub struct AlertsMenu(Submenu<Wry>);
+
+/// 遷移を記録したあとや確認・消去のあとに呼ぶ
+pub fn refresh_alerts_menu(app: &tauri::AppHandle) {
+    let Some(menu) = app.try_state::<AlertsMenu>() else { return; };
+    if let Err(e) = fill_alerts_menu(app, &menu.0) {
+        eprintln!("failed to rebuild alerts menu: {}", e);
+    }
+}
+
+fn fill_alerts_menu(app: &tauri::AppHandle, sub: &Submenu<Wry>) -> tauri::Result<()> {
+    for item in sub.items()? {
+        sub.remove(&item)?;
+    }
+
+    let Some(log) = app.try_state::<Mutex<AlertLog>>() else { return Ok(()); };
+    let (recent, active) = {
+        let log = log.lock().unwrap();
+        (log.recent(RECENT_ALERTS), log.active())
+    };
+
+    // Warning 以上のままのものは確認できる（チェック = 確認済み）
+    for (t, acked) in &active {
+        let id = format!("{}{}", ALERT_ACK_ID_PREFIX, t.metric);
+        let text = format!("Acknowledge {} ({:?})", t.label, t.level);
+        sub.append(&CheckMenuItem::with_id(app, id, text, !acked, *acked, None::<&str>)?)?;
+    }
+    if active.len() > 1 {
+        sub.append(&MenuItem::with_id(app, "alert_ack_all", "Acknowledge All", true, None::<&str>)?)?;
+    }
+    if !active.is_empty() {
+        sub.append(&PredefinedMenuItem::separator(app)?)?;
+    }
+
+    if recent.is_empty() {
+        sub.append(&MenuItem::with_id(app, "alert_none", "No Alerts", false, None::<&str>)?)?;
+    }
+    for (i, t) in recent.iter().enumerate() {
+        let text = format!("{}  {} {} {:?} ({})", alert_time(t.ts_ms), t.level.icon(&IconTheme::Emoji), t.label, t.level, t.value_text());
+        sub.append(&MenuItem::with_id(app, format!("alert_entry:{}", i), text, false, None::<&str>)?)?;
+    }
+
+    sub.append(&PredefinedMenuItem::separator(app)?)?;
+    sub.append(&MenuItem::with_id(app, "alert_clear", "Clear History", !recent.is_empty(), None::<&str>)?)?;
+    Ok(())
+}
+
+pub struct DndMenu(Submenu<Wry>);
+
+/// Do Not Disturb の切り替えや静かな時間帯の出入りのあとに呼ぶ
+pub fn refresh_dnd_menu(app: &tauri::AppHandle) {
+    let Some(menu) = app.try_state::<DndMenu>() else { return; };
+    if let Err(e) = fill_dnd_menu(app, &menu.0) {
+        eprintln!("failed to rebuild do not disturb menu: {}", e);
+    }
+}
 
-        let Ok(json) = serde_json::to_string_pretty(&cfg) else { return; };
+fn fill_dnd_menu(app: &tauri::AppHandle, sub: &Submenu<Wry>) -> tauri::Result<()> {
+    for item in sub.items()? {
+        sub.remove(&item)?;
+    }
 
-        let _ = fs::create_dir_all(&dir).await;
-        let _ = fs::write(path, json).await;
+    let until = app.try_state::<DoNotDisturb>().and_then(|dnd| dnd.until());
+    let quiet_hours = app
+        .try_state::<TrayUiState>()
+        .is_some_and(|s| quiet::in_quiet_hours(&s.config.lock().unwrap().alerts.quiet.windows, chrono::Local::now().time()));
+    let title = match until {
+        Some(t) => format!("Do Not Disturb (until {})", alert_time(t)),
+        None if quiet_hours => "Do Not Disturb (quiet hours)".to_string(),
+        None => "Do Not Disturb".to_string(),
+    };
+    sub.set_text(title)?;
+
+    sub.append(&MenuItem::with_id(app, "dnd_30m", "For 30 Minutes", true, None::<&str>)?)?;
+    sub.append(&MenuItem::with_id(app, "dnd_1h", "For 1 Hour", true, None::<&str>)?)?;
+    sub.append(&MenuItem::with_id(app, "dnd_tomorrow", "Until Tomorrow", true, None::<&str>)?)?;
+    sub.append(&PredefinedMenuItem::separator(app)?)?;
+    sub.append(&MenuItem::with_id(app, "dnd_off", "Turn Off", until.is_some(), None::<&str>)?)?;
+    Ok(())
+}
+
+// 今日なら時刻だけ、それより前は日付も付ける
+fn alert_time(ts_ms: u64) -> String {
+    let Some(t) = chrono::DateTime::from_timestamp_millis(ts_ms as i64) else { return String::new(); };
+    let t = t.with_timezone(&chrono::Local);
+    if t.date_naive() == chrono::Local::now().date_naive() {
+        t.format("%H:%M").to_string()
+    } else {
+        t.format("%m/%d %H:%M").to_string()
+    }
+}
+
+async fn custom_icons_dialog(app: tauri::AppHandle) {
+    let ui_state = app.state::<TrayUiState>();
+    let current = match &ui_state.config.lock().unwrap().icon_theme {
+        IconTheme::Custom(set) => set.clone(),
+        theme => IconSet::from_theme(theme),
+    };
+    let prompt = "Icons for Safe, Normal, Warning and Critical, separated by spaces:";
+    let answer = ui::dialog::ask_text(prompt, &current.to_text()).await;
+
+    let cfg_copy = match answer.as_deref().map(IconSet::parse) {
+        Some(Some(set)) => {
+            let mut cfg = ui_state.config.lock().unwrap();
+            cfg.icon_theme = IconTheme::Custom(set);
+            Some(cfg.clone())
+        }
+        Some(None) => {
+            ui::notify::notify("Vital Monitor", "Enter exactly four icons separated by spaces.");
+            None
+        }
+        None => None,
+    };
+    // キャンセルしたときもクリックで付いたチェックを元に戻す
+    ui_state.sync_menu_checks();
+    if let Some(cfg) = cfg_copy {
+        crate::config::store::save(&app, cfg);
+    }
+}
+
+async fn save_profile_dialog(app: tauri::AppHandle) {
+    let current = app.state::<ProfileState>().active().unwrap_or_default();
+    let Some(name) = ui::dialog::ask_text("Save the current settings as profile:", &current).await else { return; };
+    if let Err(e) = profiles::export_current(&app, &name) {
+        report_profile_error(&e);
+    }
+}
+
+async fn export_profile_dialog(app: tauri::AppHandle) {
+    let name = app.state::<ProfileState>().active().unwrap_or_else(|| "Vital Monitor".to_string());
+    let default_name = format!("{}.json", name);
+    let Some(path) = ui::dialog::choose_save_path("Export the current settings as", &default_name).await else { return; };
+    match profiles::export_to_file(&app, &path) {
+        Ok(()) => ui::notify::notify("Vital Monitor", &format!("Exported settings to {}", path.display())),
+        Err(e) => report_profile_error(&e),
+    }
+}
+
+async fn import_profile_dialog(app: tauri::AppHandle) {
+    let Some(path) = ui::dialog::choose_file("Choose a profile to import").await else { return; };
+    if let Err(e) = profiles::import(&app, &path) {
+        report_profile_error(&e);
+    }
+}
+
+async fn export_history_dialog(app: tauri::AppHandle) {
+    let Some(answer) = ui::dialog::ask_text("Export how many minutes of history?", "15").await else { return; };
+    let minutes = match answer.trim().parse::<u64>() {
+        Ok(m) if m > 0 => m,
+        _ => {
+            ui::notify::notify("Vital Monitor: export failed", &format!("not a number of minutes: {}", answer));
+            return;
+        }
+    };
+
+    let default_name = format!("vital-monitor-{}.csv", chrono::Local::now().format("%Y%m%d-%H%M"));
+    let Some(path) = ui::dialog::choose_save_path("Save history as CSV", &default_name).await else { return; };
+
+    match crate::export::history_csv::export_history(&app, minutes, &path).await {
+        Ok(rows) => ui::notify::notify("Vital Monitor", &format!("Exported {} samples to {}", rows, path.display())),
+        Err(e) => ui::notify::notify("Vital Monitor: export failed", &e),
+    }
+}
+
+fn report_profile_error(e: &crate::config::schema::ConfigError) {
+    eprintln!("profile: {}", e);
+    ui::notify::notify("Vital Monitor: profile error", &e.to_string());
+}
+
+const POPOVER_LABEL: &str = "main";
+// フォーカスが外れて閉じた直後のクリックは「閉じる」操作とみなす
+const REOPEN_GUARD: Duration = Duration::from_millis(250);
+
+pub struct PopoverState {
+    last_hidden: Mutex<Option<Instant>>,
+}
+
+fn setup_popover(app: &App) {
+    app.manage(PopoverState { last_hidden: Mutex::new(None) });
+
+    let Some(window) = app.get_webview_window(POPOVER_LABEL) else { return; };
+    let app_handle = app.handle().clone();
+    window.on_window_event(move |event| {
+        if let WindowEvent::Focused(false) = event {
+            hide_popover(&app_handle);
+        }
     });
-}
\ No newline at end of file
+}
+
+fn hide_popover(app: &tauri::AppHandle) {
+    let Some(window) = app.get_webview_window(POPOVER_LABEL) else { return; };
+    if window.is_visible().unwrap_or(false) {
+        let _ = window.hide();
+        *app.state::<PopoverState>().last_hidden.lock().unwrap() = Some(Instant::now());
+    }
+}
+
+fn toggle_popover(app: &tauri::AppHandle, icon_rect: Rect) {
+    let Some(window) = app.get_webview_window(POPOVER_LABEL) else { return; };
+
+    if window.is_visible().unwrap_or(false) {
+        hide_popover(app);
+        return;
+    }
+
+    // アイコンをクリックした瞬間に blur で閉じている場合は開き直さない
+    let recently_hidden = app
+        .state::<PopoverState>()
+        .last_hidden
+        .lock()
+        .unwrap()
+        .is_some_and(|t| t.elapsed() < REOPEN_GUARD);
+    if recently_hidden {
+        return;
+    }
+
+    let _ = window.set_position(popover_position(&window, icon_rect));
+    let _ = window.show();
+    let _ = window.set_focus();
+}
+
+// アイコンの真下・中央揃えに置く
+fn popover_position(window: &tauri::WebviewWindow, icon_rect: Rect) -> PhysicalPosition<f64> {
+    let scale = window.scale_factor().unwrap_or(1.0);
+    let icon_pos = icon_rect.position.to_physical::<f64>(scale);
+    let icon_size = icon_rect.size.to_physical::<f64>(scale);
+    let win_width = window.outer_size().map(|s| s.width as f64).unwrap_or(0.0);
+
+    PhysicalPosition::new(
+        icon_pos.x + icon_size.width / 2.0 - win_width / 2.0,
+        icon_pos.y + icon_size.height,
+    )
+}
@@ -3,18 +3,193 @@ use serde::{Serialize, Deserialize};
 use tauri::menu::CheckMenuItem;
 use tauri::Wry;
 
-use crate::metrics::types::{Percent, Millisecond, MetricsSnapshot};
+use crate::config::migrate::CURRENT_VERSION;
+use crate::config::schema::{AlertsConfig, AnomalyConfig, ExportConfig, ProcessWatch, SamplingConfig, ThresholdConfig, TrendConfig};
+use crate::metrics::types::{Percent, Millisecond, MetricsSnapshot, ProcessSample};
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
-pub enum DisplayMode { List, Rotation }
+pub enum DisplayMode { List, Rotation, Summary }
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+/// タイトルの詰め具合
+/// - UltraCompact: `34·52·18`
+/// - Compact: `C34% M52% N18ms`
+/// - Verbose: `CPU 34% Mem 52% NW 18ms`
+///
+/// それぞれ templates / compact_templates / ultra_compact_templates で書き換えられる
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Density { UltraCompact, Compact, #[default] Verbose }
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MetricKind { Cpu, Mem, Nw }
+
+impl MetricKind {
+  pub const ALL: [MetricKind; 3] = [MetricKind::Cpu, MetricKind::Mem, MetricKind::Nw];
+
+  pub fn label(self) -> &'static str {
+    match self {
+      MetricKind::Cpu => "CPU",
+      MetricKind::Mem => "Mem",
+      MetricKind::Nw => "NW",
+    }
+  }
+
+  /// 外部に出すときの名前（Prometheus のラベルや Webhook など）
+  pub fn key(self) -> &'static str {
+    match self {
+      MetricKind::Cpu => "cpu",
+      MetricKind::Mem => "mem",
+      MetricKind::Nw => "nw",
+    }
+  }
+}
+
+/// tray_config.json の中身。フィールドが欠けていても既定値で補う
+/// 形式を変えるときは config::migrate に移行処理を足して version を上げる
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct TrayConfig {
+  pub version: u32,
   pub show_cpu: bool,
   pub show_mem: bool,
   pub show_nw: bool,
   pub mode: DisplayMode,
   pub is_alert: bool,
+  // ローテーションの表示順（書かれていないものは後ろに回る）と1項目あたりの表示秒数
+  pub rotation_order: Vec<MetricKind>,
+  pub rotation_secs: u64,
+  // Critical のメトリクスがあればローテーションせずに固定表示する
+  pub pin_critical: bool,
+  // タイトルの書式（updater::template を参照）。density ごとに持つ
+  pub templates: TitleTemplates,
+  pub compact_templates: TitleTemplates,
+  pub ultra_compact_templates: TitleTemplates,
+  pub icon_theme: IconTheme,
+  pub density: Density,
+  // タイトルの最大文字数。超える場合は深刻度の低いメトリクスから落とす
+  pub max_width: Option<usize>,
+  pub thresholds: ThresholdConfig,
+  pub trends: TrendConfig,
+  pub anomaly: AnomalyConfig,
+  // CPU/Mem/NW と並べて見張るプロセス
+  pub processes: Vec<ProcessWatch>,
+  pub sampling: SamplingConfig,
+  pub export: ExportConfig,
+  pub alerts: AlertsConfig,
+}
+
+impl Default for TrayConfig {
+  fn default() -> Self {
+    Self {
+      version: CURRENT_VERSION,
+      show_cpu: true,
+      show_mem: true,
+      show_nw: true,
+      mode: DisplayMode::List,
+      is_alert: true,
+      rotation_order: MetricKind::ALL.to_vec(),
+      rotation_secs: 5,
+      pin_critical: false,
+      templates: TitleTemplates::default(),
+      compact_templates: TitleTemplates::compact(),
+      ultra_compact_templates: TitleTemplates::ultra_compact(),
+      icon_theme: IconTheme::default(),
+      density: Density::default(),
+      max_width: None,
+      thresholds: ThresholdConfig::default(),
+      trends: TrendConfig::default(),
+      anomaly: AnomalyConfig::default(),
+      processes: Vec::new(),
+      sampling: SamplingConfig::default(),
+      export: ExportConfig::default(),
+      alerts: AlertsConfig::default(),
+    }
+  }
+}
+
+/// メトリクス1項目分のテンプレート。値が取れないときは missing を使う
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MetricTemplate {
+  pub text: String,
+  pub missing: String,
+}
+
+impl Default for MetricTemplate {
+  fn default() -> Self {
+    Self::new("", "--")
+  }
+}
+
+impl MetricTemplate {
+  fn new(text: &str, missing: &str) -> Self {
+    Self { text: text.to_string(), missing: missing.to_string() }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TitleTemplates {
+  pub cpu: MetricTemplate,
+  pub mem: MetricTemplate,
+  pub nw: MetricTemplate,
+  // リスト表示で項目の間に入れる文字列
+  pub separator: String,
+  // 指定するとリスト表示を1行まるごとこのテンプレートで描画する
+  pub line: Option<String>,
+}
+
+impl Default for TitleTemplates {
+  fn default() -> Self {
+    Self {
+      cpu: MetricTemplate::new("{cpu.icon} CPU {cpu}%", "CPU --"),
+      mem: MetricTemplate::new("{mem.icon} Mem {mem}%", "Mem --"),
+      nw: MetricTemplate::new("{nw.icon} NW {nw}ms", "NW --"),
+      separator: " ".to_string(),
+      line: None,
+    }
+  }
+}
+
+impl TitleTemplates {
+  pub fn compact() -> Self {
+    Self {
+      cpu: MetricTemplate::new("{cpu.icon}C{cpu}%", "C--"),
+      mem: MetricTemplate::new("{mem.icon}M{mem}%", "M--"),
+      nw: MetricTemplate::new("{nw.icon}N{nw}ms", "N--"),
+      separator: " ".to_string(),
+      line: None,
+    }
+  }
+
+  // 数字だけ（アイコンも付けない）
+  pub fn ultra_compact() -> Self {
+    Self {
+      cpu: MetricTemplate::new("{cpu}", "--"),
+      mem: MetricTemplate::new("{mem}", "--"),
+      nw: MetricTemplate::new("{nw}", "--"),
+      separator: "·".to_string(),
+      line: None,
+    }
+  }
+}
+
+impl TrayConfig {
+  /// density を反映した実際に使うテンプレート
+  pub fn active_templates(&self) -> TitleTemplates {
+    match self.density {
+      Density::UltraCompact => self.ultra_compact_templates.clone(),
+      Density::Compact => self.compact_templates.clone(),
+      Density::Verbose => self.templates.clone(),
+    }
+  }
+
+  pub fn is_shown(&self, kind: MetricKind) -> bool {
+    match kind {
+      MetricKind::Cpu => self.show_cpu,
+      MetricKind::Mem => self.show_mem,
+      MetricKind::Nw => self.show_nw,
+    }
+  }
 }
 
 type CheckItem = CheckMenuItem<Wry>;
@@ -34,12 +209,23 @@ pub struct TrayUiState {
 
   pub mi_mode_list: CheckItem,
   pub mi_mode_rotation: CheckItem,
+  pub mi_mode_summary: CheckItem,
+  pub mi_pin_critical: CheckItem,
   pub mi_is_alert: CheckItem,
+  pub mi_theme_emoji: CheckItem,
+  pub mi_theme_ascii: CheckItem,
+  pub mi_theme_bars: CheckItem,
+  pub mi_theme_colorblind: CheckItem,
+  pub mi_theme_custom: CheckItem,
+
+  pub mi_density_ultra: CheckItem,
+  pub mi_density_compact: CheckItem,
+  pub mi_density_verbose: CheckItem,
 }
 
 impl TrayUiState {
   pub fn sync_menu_checks(&self) {
-    let cfg = *self.config.lock().unwrap();
+    let cfg = self.config.lock().unwrap().clone();
 
     let _ = self.mi_show_cpu.set_checked(false);
     let _ = self.mi_show_mem.set_checked(false);
@@ -61,28 +247,114 @@ impl TrayUiState {
 
     let _ = self.mi_mode_list.set_checked(cfg.mode == DisplayMode::List);
     let _ = self.mi_mode_rotation.set_checked(cfg.mode == DisplayMode::Rotation);
+    let _ = self.mi_mode_summary.set_checked(cfg.mode == DisplayMode::Summary);
+    let _ = self.mi_pin_critical.set_checked(cfg.pin_critical);
+    let _ = self.mi_density_ultra.set_checked(cfg.density == Density::UltraCompact);
+    let _ = self.mi_density_compact.set_checked(cfg.density == Density::Compact);
+    let _ = self.mi_density_verbose.set_checked(cfg.density == Density::Verbose);
 
     let _ = self.mi_is_alert.set_checked(cfg.is_alert);
+    let _ = self.mi_theme_emoji.set_checked(cfg.icon_theme == IconTheme::Emoji);
+    let _ = self.mi_theme_ascii.set_checked(cfg.icon_theme == IconTheme::Ascii);
+    let _ = self.mi_theme_bars.set_checked(cfg.icon_theme == IconTheme::Bars);
+    let _ = self.mi_theme_colorblind.set_checked(cfg.icon_theme == IconTheme::ColorblindSafe);
+    let _ = self.mi_theme_custom.set_checked(matches!(cfg.icon_theme, IconTheme::Custom(_)));
   }
 }
 
 /// 
 ///  
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum AlertLevel { Safe, Normal, Warning, Critical }
 
 impl AlertLevel {
-    pub fn icon(self) -> &'static str {
-        match self {
-            AlertLevel::Safe => "🔵",
-            AlertLevel::Normal => "🟢",
-            AlertLevel::Warning => "🟤",
-            AlertLevel::Critical => "🔴",
+    pub fn icon(self, theme: &IconTheme) -> &str {
+        match theme {
+            IconTheme::Emoji => match self {
+                AlertLevel::Safe => "🔵",
+                AlertLevel::Normal => "🟢",
+                AlertLevel::Warning => "🟡",
+                AlertLevel::Critical => "🔴",
+            },
+            IconTheme::Ascii => match self {
+                // 空にすると項目の頭がそろわないので何か置く
+                AlertLevel::Safe => "_",
+                AlertLevel::Normal => ".",
+                AlertLevel::Warning => "!",
+                AlertLevel::Critical => "!!",
+            },
+            IconTheme::Bars => match self {
+                AlertLevel::Safe => "▁",
+                AlertLevel::Normal => "▃",
+                AlertLevel::Warning => "▅",
+                AlertLevel::Critical => "▇",
+            },
+            // 色だけに頼らず形でも区別できるようにする（青/白/橙/停止標識）
+            IconTheme::ColorblindSafe => match self {
+                AlertLevel::Safe => "🔵",
+                AlertLevel::Normal => "⚪",
+                AlertLevel::Warning => "🔶",
+                AlertLevel::Critical => "🛑",
+            },
+            IconTheme::Custom(set) => match self {
+                AlertLevel::Safe => &set.safe,
+                AlertLevel::Normal => &set.normal,
+                AlertLevel::Warning => &set.warning,
+                AlertLevel::Critical => &set.critical,
+            },
+        }
+    }
+}
+
+/// アラートアイコンの見た目
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IconTheme {
+    #[default]
+    Emoji,
+    Ascii,
+    Bars,
+    ColorblindSafe,
+    Custom(IconSet),
+}
+
+/// レベルごとに任意の文字列を割り当てる（IconTheme::Custom 用）
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IconSet {
+    pub safe: String,
+    pub normal: String,
+    pub warning: String,
+    pub critical: String,
+}
+
+impl IconSet {
+    /// 既存のテーマを元にする（トレイの Custom… で最初に出す値）
+    pub fn from_theme(theme: &IconTheme) -> Self {
+        Self {
+            safe: AlertLevel::Safe.icon(theme).to_string(),
+            normal: AlertLevel::Normal.icon(theme).to_string(),
+            warning: AlertLevel::Warning.icon(theme).to_string(),
+            critical: AlertLevel::Critical.icon(theme).to_string(),
         }
     }
+
+    /// 空白区切りで Safe Normal Warning Critical の順
+    pub fn parse(text: &str) -> Option<Self> {
+        let icons: Vec<&str> = text.split_whitespace().collect();
+        let [safe, normal, warning, critical] = icons[..] else { return None; };
+        Some(Self {
+            safe: safe.to_string(),
+            normal: normal.to_string(),
+            warning: warning.to_string(),
+            critical: critical.to_string(),
+        })
+    }
+
+    pub fn to_text(&self) -> String {
+        format!("{} {} {} {}", self.safe, self.normal, self.warning, self.critical)
+    }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
 pub struct Metric<V> {
     pub value: V,
     pub level: AlertLevel,
@@ -104,16 +376,49 @@ pub trait Policy<V> {
     fn level(&self, value: &V) -> AlertLevel;
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct AlertThresholds {
     pub normal_lower_limit: f32,
     pub warning_lower_limit: f32,
     pub critical_lower_limit: f32,
+    // そのレベルになるのに下限以上が続く必要のある秒数（0 なら今の値だけで決まる）
+    // 履歴から判定する（alerts::sustained を参照）
+    #[serde(default)]
+    pub normal_for_secs: u64,
+    #[serde(default)]
+    pub warning_for_secs: u64,
+    #[serde(default)]
+    pub critical_for_secs: u64,
 }
 
 impl AlertThresholds {
     pub const fn new(normal: f32, warning: f32, critical: f32) -> Self {
-        Self { normal_lower_limit: normal, warning_lower_limit: warning, critical_lower_limit: critical }
+        Self {
+            normal_lower_limit: normal,
+            warning_lower_limit: warning,
+            critical_lower_limit: critical,
+            normal_for_secs: 0,
+            warning_for_secs: 0,
+            critical_for_secs: 0,
+        }
+    }
+
+    /// 深刻な順に (レベル, 下限, 継続秒数)
+    pub fn levels(&self) -> [(AlertLevel, f32, u64); 3] {
+        [
+            (AlertLevel::Critical, self.critical_lower_limit, self.critical_for_secs),
+            (AlertLevel::Warning, self.warning_lower_limit, self.warning_for_secs),
+            (AlertLevel::Normal, self.normal_lower_limit, self.normal_for_secs),
+        ]
+    }
+
+    /// normal <= warning <= critical になっているか
+    pub fn is_ordered(&self) -> bool {
+        self.normal_lower_limit <= self.warning_lower_limit && self.warning_lower_limit <= self.critical_lower_limit
+    }
+
+    pub fn longest_for_secs(&self) -> u64 {
+        self.normal_for_secs.max(self.warning_for_secs).max(self.critical_for_secs)
     }
 }
 
@@ -193,20 +498,168 @@ pub struct Policies {
     pub nw: NetworkPolicy,
 }
 
-#[derive(Clone, Debug, Default)]
+/// 1回の値ではなく履歴の傾きなどから決まるアラート
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct DerivedAlert {
+    // "mem_rise" など。遷移や Webhook ではこの名前で区別する
+    pub name: String,
+    // トレイや通知に出す名前
+    pub label: String,
+    // 複合ルールのように値を持たないものは None
+    pub value: Option<f64>,
+    pub unit: String,
+    pub level: AlertLevel,
+}
+
+impl DerivedAlert {
+    /// 小さい値（%/min など）は小数1桁まで出す
+    pub fn value_text(&self) -> Option<String> {
+        self.value.map(|v| {
+            if v.abs() < 10.0 {
+                format!("{:.1}{}", v, self.unit)
+            } else {
+                format!("{:.0}{}", v, self.unit)
+            }
+        })
+    }
+}
+
+pub const GB: f64 = 1024.0 * 1024.0 * 1024.0;
+// 遷移や Webhook でのプロセスの名前は "proc:<name>:cpu" / "proc:<name>:rss"
+const PROC_KEY_PREFIX: &str = "proc:";
+
+/// 見張っているプロセス1つ分。動いていなければ cpu/rss_gb は None
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ProcessMetric {
+    pub name: String,
+    pub count: usize,
+    pub cpu: Option<Metric<Percent>>,
+    pub rss_gb: Option<Metric<f32>>,
+}
+
+impl ProcessMetric {
+    pub fn classify(sample: &ProcessSample, watch: &ProcessWatch) -> Self {
+        Self {
+            name: sample.name.clone(),
+            count: sample.count,
+            cpu: sample.cpu_pct.map(|v| Metric::new(Percent(v), level_by_threshold(v, watch.cpu))),
+            rss_gb: sample.rss_bytes.map(|b| {
+                let gb = (b as f64 / GB) as f32;
+                Metric::new(gb, level_by_threshold(gb, watch.rss_gb))
+            }),
+        }
+    }
+
+    /// CPU と RSS の悪い方
+    pub fn level(&self) -> Option<AlertLevel> {
+        self.cpu.map(|m| m.level).max(self.rss_gb.map(|m| m.level))
+    }
+
+    pub fn cpu_key(&self) -> String {
+        format!("{}{}:cpu", PROC_KEY_PREFIX, self.name)
+    }
+
+    pub fn rss_key(&self) -> String {
+        format!("{}{}:rss", PROC_KEY_PREFIX, self.name)
+    }
+
+    fn level_mut(&mut self, key: &str) -> Option<&mut AlertLevel> {
+        if key == self.cpu_key() {
+            self.cpu.as_mut().map(|m| &mut m.level)
+        } else if key == self.rss_key() {
+            self.rss_gb.as_mut().map(|m| &mut m.level)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
 pub struct ClassifiedSnapshot {
     pub cpu: Option<Metric<Percent>>,
     pub mem: Option<Metric<Percent>>,
     pub nw: Option<Metric<Millisecond>>,
+    pub nw_p95: Option<Millisecond>,
+    // 履歴から求めたアラート（alerts::evaluate で足す）
+    pub derived: Vec<DerivedAlert>,
+    // 見張っているプロセス（閾値は設定の processes ごと。alerts::evaluate で足す）
+    pub procs: Vec<ProcessMetric>,
 }
 impl ClassifiedSnapshot {
-    pub fn new(snapshot: MetricsSnapshot) -> Self {
-        let policies = Policies::default();
+    pub fn new(snapshot: MetricsSnapshot, policies: &Policies) -> Self {
         Self {
             cpu: snapshot.cpu_pct.map(|v| Metric::classify(Percent(v), &policies.cpu)),
             mem: snapshot.mem_pressure_pct.map(|v| Metric::classify(Percent(v), &policies.mem)),
             nw: snapshot.nw_ms.map(|v| Metric::classify(Millisecond(v as f32), &policies.nw)),
+            nw_p95: snapshot.nw_p95_ms.map(|v| Millisecond(v as f32)),
+            derived: Vec::new(),
+            procs: Vec::new(),
+        }
+    }
+
+    /// Warning 以上の派生アラート（深刻な順）
+    pub fn alerting_derived(&self) -> Vec<&DerivedAlert> {
+        let mut alerting: Vec<&DerivedAlert> = self.derived.iter().filter(|d| d.level >= AlertLevel::Warning).collect();
+        alerting.sort_by_key(|d| std::cmp::Reverse(d.level));
+        alerting
+    }
+
+    /// 指定したメトリクスの中で一番悪いレベルとその原因。同じレベルなら先に並んでいる方
+    pub fn worst_of(&self, kinds: impl IntoIterator<Item = MetricKind>) -> Option<(MetricKind, AlertLevel)> {
+        kinds
+            .into_iter()
+            .filter_map(|kind| self.level_of(kind).map(|level| (kind, level)))
+            .fold(None, |worst, (kind, level)| match worst {
+                Some((_, w)) if w >= level => worst,
+                _ => Some((kind, level)),
+            })
+    }
+
+    /// メトリクス（MetricKind::key）・プロセス・派生アラートの名前でレベルを書き換える
+    pub fn level_mut(&mut self, name: &str) -> Option<&mut AlertLevel> {
+        match name {
+            "cpu" => self.cpu.as_mut().map(|m| &mut m.level),
+            "mem" => self.mem.as_mut().map(|m| &mut m.level),
+            "nw" => self.nw.as_mut().map(|m| &mut m.level),
+            _ if name.starts_with(PROC_KEY_PREFIX) => self.procs.iter_mut().find_map(|p| p.level_mut(name)),
+            _ => self.derived.iter_mut().find(|d| d.name == name).map(|d| &mut d.level),
+        }
+    }
+
+    pub fn level_of(&self, kind: MetricKind) -> Option<AlertLevel> {
+        match kind {
+            MetricKind::Cpu => self.cpu.map(|m| m.level),
+            MetricKind::Mem => self.mem.map(|m| m.level),
+            MetricKind::Nw => self.nw.map(|m| m.level),
         }
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn custom_icon_sets_round_trip_through_text() {
+        let set = IconSet::from_theme(&IconTheme::Ascii);
+        assert_eq!(set.to_text(), "_ . ! !!");
+        assert_eq!(IconSet::parse(&set.to_text()), Some(set));
+        assert_eq!(IconSet::parse("a b c"), None);
+    }
+
+    #[test]
+    fn icons_follow_the_active_theme() {
+        assert_eq!(AlertLevel::Safe.icon(&IconTheme::Ascii), "_");
+        assert_eq!(AlertLevel::Critical.icon(&IconTheme::Ascii), "!!");
+        assert_eq!(AlertLevel::Warning.icon(&IconTheme::Emoji), "🟡");
+
+        let custom = IconTheme::Custom(IconSet {
+            safe: "ok".into(),
+            normal: "~".into(),
+            warning: "?".into(),
+            critical: "X".into(),
+        });
+        assert_eq!(AlertLevel::Safe.icon(&custom), "ok");
+        assert_eq!(AlertLevel::Critical.icon(&custom), "X");
+    }
+}
@@ -1 +1,3 @@
-pub mod tray_updater;
\ No newline at end of file
+pub mod tray_updater;
+pub mod rotation;
+pub mod template;
\ No newline at end of file
@@ -0,0 +1,155 @@
+use std::time::{Duration, Instant};
+
+use crate::ui::types::{AlertLevel, ClassifiedSnapshot, MetricKind, TrayConfig};
+
+/// ローテーション表示で「今どのメトリクスを出すか」を決める
+/// 描画間隔とは独立に、1項目ごとに `rotation_secs` だけ表示し続ける
+pub struct RotationScheduler {
+    current: Option<MetricKind>,
+    shown_since: Instant,
+}
+
+impl Default for RotationScheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RotationScheduler {
+    pub fn new() -> Self {
+        Self { current: None, shown_since: Instant::now() }
+    }
+
+    pub fn select(&mut self, cfg: &TrayConfig, s: &ClassifiedSnapshot, now: Instant) -> Option<MetricKind> {
+        let order = full_order(&cfg.rotation_order);
+        // 表示ON かつ 値が取れているものだけを候補にする（None はスキップ）
+        let mut candidates: Vec<MetricKind> = order
+            .iter()
+            .copied()
+            .filter(|kind| cfg.is_shown(*kind) && s.level_of(*kind).is_some())
+            .collect();
+
+        // Critical 固定が有効なら Critical のものだけで回す
+        if cfg.pin_critical {
+            let critical: Vec<MetricKind> = candidates
+                .iter()
+                .copied()
+                .filter(|k| s.level_of(*k) == Some(AlertLevel::Critical))
+                .collect();
+            if !critical.is_empty() {
+                candidates = critical;
+            }
+        }
+
+        if candidates.is_empty() {
+            self.current = None;
+            return None;
+        }
+
+        let dwell = Duration::from_secs(cfg.rotation_secs.max(1));
+        if let Some(cur) = self.current {
+            if candidates.contains(&cur) && now.duration_since(self.shown_since) < dwell {
+                return Some(cur);
+            }
+        }
+
+        let next = self.next_after(&order, &candidates);
+        self.current = Some(next);
+        self.shown_since = now;
+        Some(next)
+    }
+
+    // rotation_order 上で現在の項目の次にある候補を探す（末尾まで行ったら先頭に戻る）
+    fn next_after(&self, order: &[MetricKind], candidates: &[MetricKind]) -> MetricKind {
+        let start = self
+            .current
+            .and_then(|cur| order.iter().position(|k| *k == cur))
+            .map(|pos| pos + 1)
+            .unwrap_or(0);
+
+        (0..order.len())
+            .map(|i| order[(start + i) % order.len()])
+            .find(|k| candidates.contains(k))
+            .unwrap_or(candidates[0])
+    }
+}
+
+// rotation_order に書かれていないメトリクスも末尾に足す（重複は先のものだけ残す）
+fn full_order(order: &[MetricKind]) -> Vec<MetricKind> {
+    let mut full: Vec<MetricKind> = Vec::with_capacity(MetricKind::ALL.len());
+    for kind in order.iter().chain(MetricKind::ALL.iter()).copied() {
+        if !full.contains(&kind) {
+            full.push(kind);
+        }
+    }
+    full
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::types::MetricsSnapshot;
+    use crate::ui::types::Policies;
+
+    fn snapshot(cpu: Option<f32>, mem: Option<f32>, nw: Option<f64>) -> ClassifiedSnapshot {
+        let snap = MetricsSnapshot { cpu_pct: cpu, mem_pressure_pct: mem, nw_ms: nw, ..MetricsSnapshot::default() };
+        ClassifiedSnapshot::new(snap, &Policies::default())
+    }
+
+    fn cfg() -> TrayConfig {
+        TrayConfig { rotation_secs: 5, ..TrayConfig::default() }
+    }
+
+    #[test]
+    fn keeps_each_item_for_the_dwell_time() {
+        let (cfg, s) = (cfg(), snapshot(Some(10.0), Some(20.0), Some(30.0)));
+        let mut rotation = RotationScheduler::new();
+        let t0 = Instant::now();
+        let at = |secs| t0 + Duration::from_secs(secs);
+
+        assert_eq!(rotation.select(&cfg, &s, at(0)), Some(MetricKind::Cpu));
+        assert_eq!(rotation.select(&cfg, &s, at(4)), Some(MetricKind::Cpu));
+        assert_eq!(rotation.select(&cfg, &s, at(5)), Some(MetricKind::Mem));
+        assert_eq!(rotation.select(&cfg, &s, at(10)), Some(MetricKind::Nw));
+        assert_eq!(rotation.select(&cfg, &s, at(15)), Some(MetricKind::Cpu));
+    }
+
+    #[test]
+    fn skips_metrics_without_a_value() {
+        let cfg = cfg();
+        let mut rotation = RotationScheduler::new();
+        let t0 = Instant::now();
+
+        let s = snapshot(Some(10.0), None, Some(30.0));
+        assert_eq!(rotation.select(&cfg, &s, t0), Some(MetricKind::Cpu));
+        assert_eq!(rotation.select(&cfg, &s, t0 + Duration::from_secs(5)), Some(MetricKind::Nw));
+        // 表示中のものが取れなくなったら待たずに次へ
+        let s = snapshot(Some(10.0), None, None);
+        assert_eq!(rotation.select(&cfg, &s, t0 + Duration::from_secs(6)), Some(MetricKind::Cpu));
+        assert_eq!(rotation.select(&cfg, &snapshot(None, None, None), t0), None);
+    }
+
+    #[test]
+    fn pins_critical_metrics() {
+        let cfg = TrayConfig { pin_critical: true, ..cfg() };
+        let s = snapshot(Some(10.0), Some(95.0), Some(30.0));
+        let mut rotation = RotationScheduler::new();
+        let t0 = Instant::now();
+        for secs in [0, 5, 10, 15] {
+            assert_eq!(rotation.select(&cfg, &s, t0 + Duration::from_secs(secs)), Some(MetricKind::Mem));
+        }
+    }
+
+    #[test]
+    fn rotates_in_metrics_missing_from_the_order() {
+        let cfg = TrayConfig { rotation_order: vec![MetricKind::Nw], ..cfg() };
+        let s = snapshot(Some(10.0), Some(20.0), Some(30.0));
+        let mut rotation = RotationScheduler::new();
+        let t0 = Instant::now();
+        let shown: Vec<Option<MetricKind>> = [0, 5, 10]
+            .into_iter()
+            .map(|secs| rotation.select(&cfg, &s, t0 + Duration::from_secs(secs)))
+            .collect();
+        assert_eq!(shown, vec![Some(MetricKind::Nw), Some(MetricKind::Cpu), Some(MetricKind::Mem)]);
+    }
+}
@@ -0,0 +1,332 @@
+//! トレイタイトル用のテンプレート
+//!
+//! 例: `"{cpu.icon}{cpu:.0}% | {nw.p95|--}ms"`
+//! - `{metric}`            値（cpu / mem / nw）
+//! - `{metric.field}`      value / icon / level / p95（p95 は nw のみ）
+//! - `{metric:.N}`         小数点以下の桁数（省略時 0）
+//! - `{metric|text}`       値が取れないときの代替テキスト（省略時 `--`）
+//! - `{{` / `}}`           波括弧そのもの
+
+use std::fmt;
+
+use crate::ui::types::{AlertLevel, ClassifiedSnapshot, MetricKind, TitleTemplates, TrayConfig};
+
+const DEFAULT_FALLBACK: &str = "--";
+const MAX_PRECISION: usize = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Field { Value, Icon, Level, P95 }
+
+#[derive(Clone, Debug, PartialEq)]
+struct Placeholder {
+    metric: MetricKind,
+    field: Field,
+    precision: usize,
+    fallback: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Segment {
+    Literal(String),
+    Field(Placeholder),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TemplateError {
+    UnclosedBrace(usize),
+    UnmatchedBrace(usize),
+    EmptyPlaceholder(usize),
+    UnknownMetric(String),
+    UnknownField(String),
+    InvalidPrecision(String),
+    UnsupportedField { metric: String, field: String },
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemplateError::UnclosedBrace(pos) => write!(f, "unclosed '{{' at {}", pos),
+            TemplateError::UnmatchedBrace(pos) => write!(f, "unmatched '}}' at {}", pos),
+            TemplateError::EmptyPlaceholder(pos) => write!(f, "empty placeholder at {}", pos),
+            TemplateError::UnknownMetric(m) => write!(f, "unknown metric '{}'", m),
+            TemplateError::UnknownField(x) => write!(f, "unknown field '{}'", x),
+            TemplateError::InvalidPrecision(p) => write!(f, "invalid precision '{}'", p),
+            TemplateError::UnsupportedField { metric, field } => {
+                write!(f, "field '{}' is not available for '{}'", field, metric)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TitleTemplate {
+    segments: Vec<Segment>,
+}
+
+impl TitleTemplate {
+    pub fn parse(src: &str) -> Result<Self, TemplateError> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = src.char_indices().peekable();
+
+        while let Some((pos, c)) = chars.next() {
+            match c {
+                '{' if matches!(chars.peek(), Some((_, '{'))) => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if matches!(chars.peek(), Some((_, '}'))) => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '}' => return Err(TemplateError::UnmatchedBrace(pos)),
+                '{' => {
+                    let mut body = String::new();
+                    let mut closed = false;
+                    for (_, c) in chars.by_ref() {
+                        if c == '}' {
+                            closed = true;
+                            break;
+                        }
+                        body.push(c);
+                    }
+                    if !closed {
+                        return Err(TemplateError::UnclosedBrace(pos));
+                    }
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+                    segments.push(Segment::Field(parse_placeholder(&body, pos)?));
+                }
+                _ => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+        Ok(Self { segments })
+    }
+
+    pub fn render(&self, cfg: &TrayConfig, s: &ClassifiedSnapshot) -> String {
+        let mut out = String::new();
+        for seg in &self.segments {
+            match seg {
+                Segment::Literal(text) => out.push_str(text),
+                Segment::Field(p) => out.push_str(&render_placeholder(p, cfg, s)),
+            }
+        }
+        out
+    }
+}
+
+// `metric[.field][:.N][|fallback]`
+fn parse_placeholder(body: &str, pos: usize) -> Result<Placeholder, TemplateError> {
+    let (spec, fallback) = match body.split_once('|') {
+        Some((spec, fb)) => (spec, Some(fb.to_string())),
+        None => (body, None),
+    };
+    let (name, precision) = match spec.split_once(':') {
+        Some((name, prec)) => (name.trim(), Some(prec.trim())),
+        None => (spec.trim(), None),
+    };
+    if name.is_empty() {
+        return Err(TemplateError::EmptyPlaceholder(pos));
+    }
+
+    let (metric_name, field_name) = match name.split_once('.') {
+        Some((m, f)) => (m, Some(f)),
+        None => (name, None),
+    };
+    let metric = match metric_name {
+        "cpu" => MetricKind::Cpu,
+        "mem" => MetricKind::Mem,
+        "nw" => MetricKind::Nw,
+        other => return Err(TemplateError::UnknownMetric(other.to_string())),
+    };
+    let field = match field_name {
+        None | Some("value") => Field::Value,
+        Some("icon") => Field::Icon,
+        Some("level") => Field::Level,
+        Some("p95") => Field::P95,
+        Some(other) => return Err(TemplateError::UnknownField(other.to_string())),
+    };
+    if field == Field::P95 && metric != MetricKind::Nw {
+        return Err(TemplateError::UnsupportedField {
+            metric: metric_name.to_string(),
+            field: "p95".to_string(),
+        });
+    }
+
+    let precision = match precision {
+        None => 0,
+        Some(p) => p
+            .strip_prefix('.')
+            .and_then(|n| n.parse::<usize>().ok())
+            .filter(|n| *n <= MAX_PRECISION)
+            .ok_or_else(|| TemplateError::InvalidPrecision(p.to_string()))?,
+    };
+
+    Ok(Placeholder { metric, field, precision, fallback })
+}
+
+fn render_placeholder(p: &Placeholder, cfg: &TrayConfig, s: &ClassifiedSnapshot) -> String {
+    let fallback = || p.fallback.clone().unwrap_or_else(|| DEFAULT_FALLBACK.to_string());
+
+    match p.field {
+        Field::Icon => match s.level_of(p.metric) {
+            Some(level) if cfg.is_alert => level.icon(&cfg.icon_theme).to_string(),
+            _ => String::new(),
+        },
+        Field::Level => match s.level_of(p.metric) {
+            Some(level) => level_name(level).to_string(),
+            None => fallback(),
+        },
+        Field::Value => match value_of(s, p.metric) {
+            Some(v) => format!("{:.*}", p.precision, v),
+            None => fallback(),
+        },
+        Field::P95 => match s.nw_p95 {
+            Some(v) => format!("{:.*}", p.precision, v.0),
+            None => fallback(),
+        },
+    }
+}
+
+fn value_of(s: &ClassifiedSnapshot, kind: MetricKind) -> Option<f32> {
+    match kind {
+        MetricKind::Cpu => s.cpu.map(|m| m.value.0),
+        MetricKind::Mem => s.mem.map(|m| m.value.0),
+        MetricKind::Nw => s.nw.map(|m| m.value.0),
+    }
+}
+
+fn level_name(level: AlertLevel) -> &'static str {
+    match level {
+        AlertLevel::Safe => "Safe",
+        AlertLevel::Normal => "Normal",
+        AlertLevel::Warning => "Warning",
+        AlertLevel::Critical => "Critical",
+    }
+}
+
+/// 設定の各テンプレートをパース済みの状態で持っておく
+pub struct CompiledTemplates {
+    source: TitleTemplates,
+    cpu: (TitleTemplate, TitleTemplate),
+    mem: (TitleTemplate, TitleTemplate),
+    nw: (TitleTemplate, TitleTemplate),
+    line: Option<TitleTemplate>,
+}
+
+impl CompiledTemplates {
+    pub fn compile(src: &TitleTemplates) -> Result<Self, TemplateError> {
+        Ok(Self {
+            source: src.clone(),
+            cpu: (TitleTemplate::parse(&src.cpu.text)?, TitleTemplate::parse(&src.cpu.missing)?),
+            mem: (TitleTemplate::parse(&src.mem.text)?, TitleTemplate::parse(&src.mem.missing)?),
+            nw: (TitleTemplate::parse(&src.nw.text)?, TitleTemplate::parse(&src.nw.missing)?),
+            line: src.line.as_deref().map(TitleTemplate::parse).transpose()?,
+        })
+    }
+
+    pub fn is_compiled_from(&self, src: &TitleTemplates) -> bool {
+        self.source == *src
+    }
+
+    pub fn separator(&self) -> &str {
+        &self.source.separator
+    }
+
+    /// リスト表示で1行まるごと置き換えるテンプレート
+    pub fn line(&self) -> Option<&TitleTemplate> {
+        self.line.as_ref()
+    }
+
+    /// メトリクス1項目分。値が無いときは missing 側を使う
+    pub fn render_item(&self, kind: MetricKind, cfg: &TrayConfig, s: &ClassifiedSnapshot) -> String {
+        let (text, missing) = match kind {
+            MetricKind::Cpu => &self.cpu,
+            MetricKind::Mem => &self.mem,
+            MetricKind::Nw => &self.nw,
+        };
+        let tpl = if s.level_of(kind).is_some() { text } else { missing };
+        tpl.render(cfg, s).trim().to_string()
+    }
+}
+
+impl Default for CompiledTemplates {
+    fn default() -> Self {
+        Self::compile(&TitleTemplates::default()).expect("default templates must be valid")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::types::MetricsSnapshot;
+    use crate::ui::types::Policies;
+
+    fn snapshot() -> ClassifiedSnapshot {
+        ClassifiedSnapshot::new(MetricsSnapshot {
+            cpu_pct: Some(34.4),
+            mem_pressure_pct: Some(91.0),
+            nw_ms: None,
+            nw_p95_ms: Some(18.34),
+            ..MetricsSnapshot::default()
+        }, &Policies::default())
+    }
+
+    #[test]
+    fn renders_values_with_precision_and_icons() {
+        let cfg = TrayConfig::default();
+        let tpl = TitleTemplate::parse("{cpu.icon}{cpu:.0}% | {mem:.1}% | {nw.p95:.1}ms").unwrap();
+        assert_eq!(tpl.render(&cfg, &snapshot()), "🔵34% | 91.0% | 18.3ms");
+    }
+
+    #[test]
+    fn missing_values_use_fallback() {
+        let cfg = TrayConfig::default();
+        let tpl = TitleTemplate::parse("{nw.icon}NW {nw}ms / {nw|n/a}").unwrap();
+        assert_eq!(tpl.render(&cfg, &snapshot()), "NW --ms / n/a");
+    }
+
+    #[test]
+    fn icons_are_hidden_when_alert_is_off() {
+        let cfg = TrayConfig { is_alert: false, ..TrayConfig::default() };
+        let tpl = TitleTemplate::parse("{mem.icon}{mem.level}").unwrap();
+        assert_eq!(tpl.render(&cfg, &snapshot()), "Critical");
+    }
+
+    #[test]
+    fn escaped_braces_are_literal() {
+        let cfg = TrayConfig::default();
+        let tpl = TitleTemplate::parse("{{cpu}} {cpu}").unwrap();
+        assert_eq!(tpl.render(&cfg, &snapshot()), "{cpu} 34");
+    }
+
+    #[test]
+    fn rejects_invalid_templates() {
+        assert_eq!(TitleTemplate::parse("{cpu"), Err(TemplateError::UnclosedBrace(0)));
+        assert_eq!(TitleTemplate::parse("cpu}"), Err(TemplateError::UnmatchedBrace(3)));
+        assert_eq!(TitleTemplate::parse("{}"), Err(TemplateError::EmptyPlaceholder(0)));
+        assert_eq!(TitleTemplate::parse("{gpu}"), Err(TemplateError::UnknownMetric("gpu".into())));
+        assert_eq!(TitleTemplate::parse("{cpu.max}"), Err(TemplateError::UnknownField("max".into())));
+        assert_eq!(TitleTemplate::parse("{cpu:2}"), Err(TemplateError::InvalidPrecision("2".into())));
+        assert_eq!(TitleTemplate::parse("{cpu:.9}"), Err(TemplateError::InvalidPrecision(".9".into())));
+        assert!(matches!(
+            TitleTemplate::parse("{cpu.p95}"),
+            Err(TemplateError::UnsupportedField { .. })
+        ));
+    }
+
+    #[test]
+    fn items_switch_to_missing_template() {
+        let cfg = TrayConfig::default();
+        let compiled = CompiledTemplates::default();
+        let s = snapshot();
+        assert_eq!(compiled.render_item(MetricKind::Cpu, &cfg, &s), "🔵 CPU 34%");
+        assert_eq!(compiled.render_item(MetricKind::Nw, &cfg, &s), "NW --");
+    }
+}
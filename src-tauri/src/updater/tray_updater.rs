@@ -1,41 +1,60 @@
+use std::sync::Mutex;
+use std::time::Instant;
 use tauri::Manager;
 
 use crate::TrayState;
-use crate::ui::types::{AlertLevel, DisplayMode, TrayConfig, ClassifiedSnapshot};
+use crate::alerts::log::AlertLog;
+use crate::ui::types::{AlertLevel, DisplayMode, MetricKind, TrayConfig, ClassifiedSnapshot};
+use crate::updater::rotation::RotationScheduler;
+use crate::updater::template::CompiledTemplates;
 
-pub fn spawn_tray_renderer(app: tauri::AppHandle, metrics: crate::metrics::types::SharedMetrics) {
+pub fn spawn_tray_renderer(app: tauri::AppHandle) {
     let render_interval = 1;
-    let rotation_interval = 5;
 
     tauri::async_runtime::spawn(async move {
         let mut render_ticker = tokio::time::interval(std::time::Duration::from_secs(render_interval));
-        let mut rotation_tick = tokio::time::interval(std::time::Duration::from_secs(rotation_interval));
-        let mut rotation_index = 0;
+        let mut rotation = RotationScheduler::new();
+        let mut templates = CompiledTemplates::default();
+        // パースできなかったテンプレート。同じものを毎秒パースし直さない
+        let mut rejected = None;
         loop {
-            tokio::select! {
-                _ = render_ticker.tick() => {
-                    let cfg: TrayConfig = match app.try_state::<crate::ui::types::TrayUiState>() {
-                        Some(st) => *st.config.lock().unwrap(),
-                        None => continue,
-                    };
-                      
-                    let snap = metrics.read().await.clone();
-                    let snap_classified = crate::ui::types::ClassifiedSnapshot::new(snap);
-                    
-                    
-                    let title = format_title(&cfg, &snap_classified, &mut rotation_index);
-                    
-                    if let Some(state) = app.try_state::<TrayState>() {
-                        if let Ok(tray) = state.tray.lock() {
-                            let _ = tray.set_title(Some(&title));
-                        }
+            render_ticker.tick().await;
+
+            let cfg: TrayConfig = match app.try_state::<crate::ui::types::TrayUiState>() {
+                Some(st) => st.config.lock().unwrap().clone(),
+                None => continue,
+            };
+
+            // 履歴を使う判定もあるので、サンプラーがレベルを付けたものを使う
+            let mut snap_classified = crate::ipc::latest_update(&app).map(|u| u.classified).unwrap_or_default();
+            if let Some(log) = app.try_state::<Mutex<AlertLog>>() {
+                log.lock().unwrap().apply_acks(&mut snap_classified);
+            }
+
+            // テンプレートは設定が変わったときだけパースし直す
+            let active = cfg.active_templates();
+            if !templates.is_compiled_from(&active) && rejected.as_ref() != Some(&active) {
+                match CompiledTemplates::compile(&active) {
+                    Ok(compiled) => {
+                        templates = compiled;
+                        rejected = None;
                     }
-                },
+                    // 既定のテンプレートで表示を続ける
+                    Err(e) => {
+                        eprintln!("invalid tray title template: {}", e);
+                        crate::ui::notify::notify("Vital Monitor: invalid title template", &e.to_string());
+                        templates = CompiledTemplates::default();
+                        rejected = Some(active);
+                    }
+                }
+            }
+
+            let title = format_title(&cfg, &snap_classified, &templates, &mut rotation);
 
-                _ = rotation_tick.tick() => {
-                    rotation_index = rotation_index.wrapping_add(1);
-                    rotation_index %= 6; // TODO: magic number
-                },
+            if let Some(state) = app.try_state::<TrayState>() {
+                if let Ok(tray) = state.tray.lock() {
+                    let _ = tray.set_title(Some(&title));
+                }
             }
         }
     });
@@ -44,73 +63,272 @@ pub fn spawn_tray_renderer(app: tauri::AppHandle, metrics: crate::metrics::types
 pub fn format_title(
     cfg: &TrayConfig,
     s: &ClassifiedSnapshot,
-    rotation_index: &mut usize,
+    templates: &CompiledTemplates,
+    rotation: &mut RotationScheduler,
 ) -> String {
     match cfg.mode {
-        DisplayMode::List => format_list(cfg, s),
-        DisplayMode::Rotation => format_rotation(cfg, s, rotation_index),
+        DisplayMode::List => format_list(cfg, s, templates),
+        DisplayMode::Rotation => with_alerting_items(cfg, s, templates, format_rotation(cfg, s, templates, rotation)),
+        DisplayMode::Summary => with_alerting_items(cfg, s, templates, format_summary(cfg, s, templates)),
     }
 }
 
-fn icon(cfg: &TrayConfig, level: AlertLevel) -> &'static str {
-    if cfg.is_alert { level.icon() } else { "" }
+/// ローテーション・サマリーでは、Warning 以上のプロセスと派生アラートだけを後ろに足す
+/// 幅の制限では先頭（今の表示）は落とさず、足した項目から落とす
+fn with_alerting_items(cfg: &TrayConfig, s: &ClassifiedSnapshot, templates: &CompiledTemplates, head: String) -> String {
+    let mut items = vec![(None, head)];
+    items.extend(process_items(cfg, s, true));
+    items.extend(derived_items(cfg, s));
+    fit(cfg, items, templates.separator(), 1)
 }
 
-fn format_list(cfg: &TrayConfig, s: &ClassifiedSnapshot) -> String {
-    let mut parts = Vec::new();
-    if cfg.show_cpu {
-        parts.push(match s.cpu {
-            Some(m) => format!("{} CPU {}%", icon(cfg, m.level), m.value),
-            None => "CPU --".into(),
-        });
+fn fit(cfg: &TrayConfig, items: Vec<(Option<AlertLevel>, String)>, sep: &str, pinned: usize) -> String {
+    match cfg.max_width {
+        Some(max) => fit_to_width(items, sep, max, pinned),
+        None => join_items(&items, sep),
     }
-    if cfg.show_mem {
-        parts.push(match s.mem {
-            Some(m) => format!("{} Mem {:.0}%", icon(cfg, m.level), m.value),
-            None => "Mem --".into(),
-        });
+}
+
+/// 見張っているプロセスの項目（例: `🔴 rust-analyzer 35% 4.2GB` / `node --`）
+/// alerting_only なら Warning 以上のものだけ。そうでなければ show が false のものは Warning 以上のときだけ
+fn process_items(cfg: &TrayConfig, s: &ClassifiedSnapshot, alerting_only: bool) -> Vec<(Option<AlertLevel>, String)> {
+    s.procs
+        .iter()
+        .filter(|p| {
+            let alerting = p.level() >= Some(AlertLevel::Warning);
+            let shown = cfg.processes.iter().any(|w| w.name == p.name && w.show);
+            alerting || (!alerting_only && shown)
+        })
+        .map(|p| {
+            let level = p.level();
+            let Some(level) = level else { return (None, format!("{} --", p.name)); };
+            let icon = if cfg.is_alert { level.icon(&cfg.icon_theme) } else { "" };
+            let cpu = p.cpu.map(|m| format!(" {}%", m.value)).unwrap_or_default();
+            let rss = p.rss_gb.map(|m| format!(" {:.1}GB", m.value)).unwrap_or_default();
+            (Some(level), format!("{} {}{}{}", icon, p.name, cpu, rss).trim_start().to_string())
+        })
+        .collect()
+}
+
+/// Warning 以上の派生アラート（上昇速度・枯渇予測など）の項目
+/// 例: `CPU 12% Mem 74% NW 20ms 🟡Mem↗ 1.5%/min` の `🟡Mem↗ 1.5%/min`
+fn derived_items(cfg: &TrayConfig, s: &ClassifiedSnapshot) -> Vec<(Option<AlertLevel>, String)> {
+    s.alerting_derived()
+        .into_iter()
+        .map(|d| {
+            let icon = if cfg.is_alert { d.level.icon(&cfg.icon_theme) } else { "" };
+            let text = match d.value_text() {
+                Some(value) => format!("{}{} {}", icon, d.label, value),
+                None => format!("{}{}", icon, d.label),
+            };
+            (Some(d.level), text)
+        })
+        .collect()
+}
+
+pub(crate) fn format_list(cfg: &TrayConfig, s: &ClassifiedSnapshot, templates: &CompiledTemplates) -> String {
+    // 1行テンプレートは項目に分けられないので幅の制限は掛けない
+    if let Some(line) = templates.line() {
+        return line.render(cfg, s);
     }
-    if cfg.show_nw {
-        parts.push(match s.nw {
-            Some(m) => format!("{} NW {:.0}ms", icon(cfg, m.level), m.value),
-            None => "NW --".into(),
-        });
+
+    let mut items: Vec<(Option<AlertLevel>, String)> = MetricKind::ALL
+        .into_iter()
+        .filter(|kind| cfg.is_shown(*kind))
+        .map(|kind| (s.level_of(kind), templates.render_item(kind, cfg, s)))
+        .collect();
+    items.extend(process_items(cfg, s, false));
+    items.extend(derived_items(cfg, s));
+    fit(cfg, items, templates.separator(), 0)
+}
+
+/// 幅に収まるまで深刻度の低い項目から落とす（同じ深刻度なら後ろの項目から）
+/// 値が取れていない項目が最優先で落ちる。先頭の pinned 個は落とさず、最低1項目は残す
+fn fit_to_width(mut items: Vec<(Option<AlertLevel>, String)>, sep: &str, max: usize, pinned: usize) -> String {
+    loop {
+        let text = join_items(&items, sep);
+        if items.len() <= pinned.max(1) || text.chars().count() <= max {
+            return text;
+        }
+        let drop_idx = items
+            .iter()
+            .enumerate()
+            .skip(pinned)
+            .rev()
+            .min_by_key(|(_, (level, _))| *level)
+            .map(|(i, _)| i)
+            .unwrap_or(items.len() - 1);
+        items.remove(drop_idx);
     }
+}
 
-    let text = parts.join(" ");
-    text
+fn join_items(items: &[(Option<AlertLevel>, String)], sep: &str) -> String {
+    items.iter().map(|(_, text)| text.as_str()).collect::<Vec<_>>().join(sep)
 }
 
-fn format_rotation(cfg: &TrayConfig, s: &ClassifiedSnapshot, rotation_index: &mut usize) -> String {
-    let mut items: Vec<String> = Vec::new();
+fn format_rotation(
+    cfg: &TrayConfig,
+    s: &ClassifiedSnapshot,
+    templates: &CompiledTemplates,
+    rotation: &mut RotationScheduler,
+) -> String {
+    match rotation.select(cfg, s, Instant::now()) {
+        Some(kind) => templates.render_item(kind, cfg, s),
+        None => "—".to_string(),
+    }
+}
 
-    if cfg.show_cpu {
-        items.push(match s.cpu {
-            Some(m) => format!("{}CPU {}%", icon(cfg, m.level), m.value),
-            None => "CPU --".to_string(),
-        });
+/// 表示ONのメトリクスで一番悪いレベルのアイコン1つと原因のメトリクス名
+/// Warning 以上のものだけ値も出す（例: `🔴 Mem 91% CPU 80%` / `🟢 NW`）
+fn format_summary(cfg: &TrayConfig, s: &ClassifiedSnapshot, templates: &CompiledTemplates) -> String {
+    let shown = MetricKind::ALL.into_iter().filter(|kind| cfg.is_shown(*kind));
+    let Some((worst_kind, worst_level)) = s.worst_of(shown.clone()) else {
+        return "—".to_string();
+    };
+    let icon = if cfg.is_alert { worst_level.icon(&cfg.icon_theme) } else { "" };
+
+    // 詳細側はアイコンを重ねない
+    let plain = TrayConfig { is_alert: false, ..cfg.clone() };
+    let mut alerting: Vec<(MetricKind, AlertLevel)> = shown
+        .filter_map(|kind| s.level_of(kind).map(|level| (kind, level)))
+        .filter(|(_, level)| *level >= AlertLevel::Warning)
+        .collect();
+    alerting.sort_by_key(|&(_, level)| std::cmp::Reverse(level));
+
+    let detail = if alerting.is_empty() {
+        worst_kind.label().to_string()
+    } else {
+        let items: Vec<String> = alerting
+            .into_iter()
+            .map(|(kind, _)| templates.render_item(kind, &plain, s))
+            .collect();
+        items.join(templates.separator())
+    };
+
+    format!("{} {}", icon, detail).trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::types::MetricsSnapshot;
+    use crate::ui::types::{DerivedAlert, Density};
+
+    fn snapshot() -> ClassifiedSnapshot {
+        let snap = MetricsSnapshot {
+            cpu_pct: Some(34.0),
+            mem_pressure_pct: Some(91.0),
+            nw_ms: Some(18.0),
+            ..MetricsSnapshot::default()
+        };
+        ClassifiedSnapshot::new(snap, &TrayConfig::default().policies())
     }
 
-    if cfg.show_mem {
-        items.push(match s.mem {
-            Some(m) => format!("{}Mem {:.0}%", icon(cfg, m.level), m.value),
-            None => "Mem --".to_string(),
-        });
+    fn list(cfg: &TrayConfig, s: &ClassifiedSnapshot) -> String {
+        let templates = CompiledTemplates::compile(&cfg.active_templates()).unwrap();
+        format_list(cfg, s, &templates)
     }
 
-    if cfg.show_nw {
-        items.push(match s.nw {
-            Some(m) => format!("{}NW {:.0}ms", icon(cfg, m.level), m.value),
-            None => "NW --".to_string(),
-        });
+    #[test]
+    fn renders_each_density() {
+        let s = snapshot();
+        let verbose = TrayConfig::default();
+        assert_eq!(list(&verbose, &s), "🔵 CPU 34% 🔴 Mem 91% 🔵 NW 18ms");
+        let compact = TrayConfig { density: Density::Compact, ..TrayConfig::default() };
+        assert_eq!(list(&compact, &s), "🔵C34% 🔴M91% 🔵N18ms");
+        let ultra = TrayConfig { density: Density::UltraCompact, ..TrayConfig::default() };
+        assert_eq!(list(&ultra, &s), "34·91·18");
     }
 
-    if items.is_empty() {
-        return "—".to_string();
+    #[test]
+    fn densities_keep_their_own_templates() {
+        let mut cfg = TrayConfig { density: Density::UltraCompact, ..TrayConfig::default() };
+        cfg.ultra_compact_templates.separator = "|".to_string();
+        assert_eq!(list(&cfg, &snapshot()), "34|91|18");
     }
 
-    let idx = *rotation_index % items.len();
-    *rotation_index = rotation_index.wrapping_add(1);
+    #[test]
+    fn drops_least_severe_items_to_fit_the_budget() {
+        let items = vec![
+            (Some(AlertLevel::Normal), "CPU 60%".to_string()),
+            (Some(AlertLevel::Critical), "Mem 95%".to_string()),
+            (None, "NW --".to_string()),
+            (Some(AlertLevel::Normal), "Disk 70%".to_string()),
+        ];
+        assert_eq!(fit_to_width(items.clone(), " ", 100, 0), "CPU 60% Mem 95% NW -- Disk 70%");
+        // 値の無いものが先に落ち、同じレベルなら後ろから落ちる
+        assert_eq!(fit_to_width(items.clone(), " ", 25, 0), "CPU 60% Mem 95% Disk 70%");
+        assert_eq!(fit_to_width(items.clone(), " ", 20, 0), "CPU 60% Mem 95%");
+        assert_eq!(fit_to_width(items.clone(), " ", 10, 0), "Mem 95%");
+        // 最低1項目は残す
+        assert_eq!(fit_to_width(items, " ", 1, 0), "Mem 95%");
+    }
 
-    items[idx].clone()
+    #[test]
+    fn worst_of_prefers_the_first_of_equal_levels() {
+        let s = snapshot();
+        assert_eq!(s.worst_of(MetricKind::ALL), Some((MetricKind::Mem, AlertLevel::Critical)));
+        assert_eq!(s.worst_of([MetricKind::Nw, MetricKind::Cpu]), Some((MetricKind::Nw, AlertLevel::Safe)));
+        assert_eq!(ClassifiedSnapshot::default().worst_of(MetricKind::ALL), None);
+    }
+
+    #[test]
+    fn summary_shows_the_worst_icon_and_alerting_details() {
+        let cfg = TrayConfig { mode: DisplayMode::Summary, ..TrayConfig::default() };
+        let templates = CompiledTemplates::compile(&cfg.active_templates()).unwrap();
+        assert_eq!(format_summary(&cfg, &snapshot(), &templates), "🔴 Mem 91%");
+
+        // Warning 以上が無ければ原因の名前だけ
+        let calm = TrayConfig { show_mem: false, ..cfg.clone() };
+        assert_eq!(format_summary(&calm, &snapshot(), &templates), "🔵 CPU");
+        assert_eq!(format_summary(&cfg, &ClassifiedSnapshot::default(), &templates), "—");
+    }
+
+    #[test]
+    fn list_honours_max_width() {
+        let cfg = TrayConfig { density: Density::Compact, max_width: Some(10), ..TrayConfig::default() };
+        assert_eq!(list(&cfg, &snapshot()), "🔴M91%");
+    }
+
+    #[test]
+    fn derived_alerts_count_against_the_budget() {
+        let mut s = snapshot();
+        s.derived.push(DerivedAlert {
+            name: "mem_rise".to_string(),
+            label: "Mem↗".to_string(),
+            value: Some(2.5),
+            unit: "%/min".to_string(),
+            level: AlertLevel::Warning,
+        });
+        let cfg = TrayConfig { density: Density::Compact, ..TrayConfig::default() };
+        assert_eq!(list(&cfg, &s), "🔵C34% 🔴M91% 🔵N18ms 🟡Mem↗ 2.5%/min");
+
+        let narrow = TrayConfig { max_width: Some(20), ..cfg };
+        assert_eq!(list(&narrow, &s), "🔴M91% 🟡Mem↗ 2.5%/min");
+    }
+
+    #[test]
+    fn summary_keeps_its_head_within_the_budget() {
+        let mut s = snapshot();
+        let derived = |name: &str, value, level| DerivedAlert {
+            name: name.to_string(),
+            label: name.to_string(),
+            value,
+            unit: "%/min".to_string(),
+            level,
+        };
+        s.derived.push(derived("Mem↗", Some(2.5), AlertLevel::Warning));
+        s.derived.push(derived("build_slow", None, AlertLevel::Critical));
+
+        let cfg = TrayConfig { mode: DisplayMode::Summary, ..TrayConfig::default() };
+        let templates = CompiledTemplates::compile(&cfg.active_templates()).unwrap();
+        let title = |max_width| {
+            let cfg = TrayConfig { max_width, ..cfg.clone() };
+            with_alerting_items(&cfg, &s, &templates, format_summary(&cfg, &s, &templates))
+        };
+        assert_eq!(title(None), "🔴 Mem 91% 🔴build_slow 🟡Mem↗ 2.5%/min");
+        assert_eq!(title(Some(21)), "🔴 Mem 91% 🔴build_slow");
+        // 今の表示は幅を超えても残す
+        assert_eq!(title(Some(5)), "🔴 Mem 91%");
+    }
 }
@@ -1,10 +1,118 @@
+import { useEffect, useState } from "react";
 import "./App.css";
+import {
+  AlertLevel,
+  ClassifiedSnapshot,
+  Sample,
+  getHistory,
+  onMetricsUpdate,
+} from "./api";
+
+// ポップオーバーに出す期間（秒）
+const WINDOW_SECS = 300;
+
+type Series = { ts: number; value: number | null }[];
+
+function pick(samples: Sample[], key: keyof Sample["snapshot"]): Series {
+  return samples.map((s) => ({ ts: s.ts_ms, value: s.snapshot[key] }));
+}
+
+function Sparkline({ series, max }: { series: Series; max: number }) {
+  const width = 320;
+  const height = 48;
+  if (series.length < 2) {
+    return <svg className="spark" width={width} height={height} />;
+  }
+  const t0 = series[0].ts;
+  const span = Math.max(series[series.length - 1].ts - t0, 1);
+  const top = Math.max(max, ...series.map((p) => p.value ?? 0));
+
+  // 値が取れていない区間は線を切る
+  let d = "";
+  let pen = false;
+  for (const p of series) {
+    if (p.value === null) {
+      pen = false;
+      continue;
+    }
+    const x = ((p.ts - t0) / span) * width;
+    const y = height - (p.value / top) * height;
+    d += `${pen ? "L" : "M"}${x.toFixed(1)},${y.toFixed(1)} `;
+    pen = true;
+  }
+
+  return (
+    <svg className="spark" width={width} height={height}>
+      <path d={d} />
+    </svg>
+  );
+}
+
+function MetricRow(props: {
+  label: string;
+  unit: string;
+  level: AlertLevel | undefined;
+  value: number | undefined;
+  series: Series;
+  max: number;
+}) {
+  const { label, unit, level, value, series, max } = props;
+  return (
+    <section className={`metric ${level?.toLowerCase() ?? "missing"}`}>
+      <header>
+        <span>{label}</span>
+        <span>{value === undefined ? "--" : `${value.toFixed(0)}${unit}`}</span>
+      </header>
+      <Sparkline series={series} max={max} />
+    </section>
+  );
+}
 
 function App() {
+  const [samples, setSamples] = useState<Sample[]>([]);
+  const [latest, setLatest] = useState<ClassifiedSnapshot | null>(null);
+
+  useEffect(() => {
+    getHistory({ Last: WINDOW_SECS }).then(setSamples);
+
+    const unlisten = onMetricsUpdate((update) => {
+      setLatest(update.classified);
+      setSamples((prev) => {
+        const from = update.ts_ms - WINDOW_SECS * 1000;
+        return [...prev.filter((s) => s.ts_ms >= from), update];
+      });
+    });
+    return () => {
+      unlisten.then((f) => f());
+    };
+  }, []);
 
   return (
     <main className="container">
-      
+      <MetricRow
+        label="CPU"
+        unit="%"
+        level={latest?.cpu?.level}
+        value={latest?.cpu?.value}
+        series={pick(samples, "cpu_pct")}
+        max={100}
+      />
+      <MetricRow
+        label="Memory"
+        unit="%"
+        level={latest?.mem?.level}
+        value={latest?.mem?.value}
+        series={pick(samples, "mem_pressure_pct")}
+        max={100}
+      />
+      <MetricRow
+        label="Network"
+        unit="ms"
+        level={latest?.nw?.level}
+        value={latest?.nw?.value}
+        series={pick(samples, "nw_ms")}
+        max={100}
+      />
     </main>
   );
 }
@@ -0,0 +1,211 @@
+import { useEffect, useState } from "react";
+import "./App.css";
+import {
+  AlertThresholds,
+  TrayConfig,
+  getConfig,
+  getConfigIssues,
+  getDefaultConfig,
+  setConfig,
+} from "./api";
+
+type ThresholdKey = keyof TrayConfig["thresholds"];
+type SamplingKey = "cpu_secs" | "mem_secs" | "nw_secs" | "nw_timeout_secs";
+
+const THRESHOLD_ROWS: [ThresholdKey, string][] = [
+  ["cpu", "CPU (%)"],
+  ["mem", "Memory (%)"],
+  ["nw", "Network (ms)"],
+];
+
+const SAMPLING_ROWS: [SamplingKey, string][] = [
+  ["cpu_secs", "CPU interval (s)"],
+  ["mem_secs", "Memory interval (s)"],
+  ["nw_secs", "Network interval (s)"],
+  ["nw_timeout_secs", "Network timeout (s)"],
+];
+
+function NumberInput(props: { value: number; onChange: (v: number) => void }) {
+  return (
+    <input
+      type="number"
+      value={props.value}
+      onChange={(e) => props.onChange(Number(e.target.value))}
+    />
+  );
+}
+
+function Settings() {
+  const [config, setLocal] = useState<TrayConfig | null>(null);
+  const [issues, setIssues] = useState<string[]>([]);
+  const [saved, setSaved] = useState(false);
+  // カンマ区切りの入力途中を保てるように文字列のまま持つ
+  const [targetsText, setTargetsText] = useState("");
+
+  const load = (cfg: TrayConfig) => {
+    setLocal(cfg);
+    setTargetsText(cfg.sampling.nw_targets.join(", "));
+  };
+
+  useEffect(() => {
+    getConfig().then(load);
+    getConfigIssues().then(setIssues);
+  }, []);
+
+  if (!config) {
+    return null;
+  }
+
+  const update = (patch: Partial<TrayConfig>) => {
+    setSaved(false);
+    setLocal({ ...config, ...patch });
+  };
+
+  const updateThreshold = (
+    key: ThresholdKey,
+    field: keyof AlertThresholds,
+    value: number,
+  ) =>
+    update({
+      thresholds: {
+        ...config.thresholds,
+        [key]: { ...config.thresholds[key], [field]: value },
+      },
+    });
+
+  const save = async () => {
+    try {
+      await setConfig(config);
+      setIssues([]);
+      setSaved(true);
+    } catch (e) {
+      setIssues(Array.isArray(e) ? e : [String(e)]);
+    }
+  };
+
+  const reset = async () => {
+    setSaved(false);
+    load(await getDefaultConfig());
+  };
+
+  return (
+    <main className="container settings">
+      {issues.length > 0 && (
+        <ul className="issues">
+          {issues.map((issue) => (
+            <li key={issue}>{issue}</li>
+          ))}
+        </ul>
+      )}
+
+      <fieldset>
+        <legend>Display</legend>
+        <label>
+          Mode
+          <select
+            value={config.mode}
+            onChange={(e) => update({ mode: e.target.value as TrayConfig["mode"] })}
+          >
+            <option>List</option>
+            <option>Rotation</option>
+            <option>Summary</option>
+          </select>
+        </label>
+        <label>
+          Density
+          <select
+            value={config.density}
+            onChange={(e) =>
+              update({ density: e.target.value as TrayConfig["density"] })
+            }
+          >
+            <option>UltraCompact</option>
+            <option>Compact</option>
+            <option>Verbose</option>
+          </select>
+        </label>
+        <label>
+          Rotation (s)
+          <NumberInput
+            value={config.rotation_secs}
+            onChange={(v) => update({ rotation_secs: v })}
+          />
+        </label>
+        <label>
+          Max width
+          <input
+            type="number"
+            value={config.max_width ?? ""}
+            placeholder="unlimited"
+            onChange={(e) =>
+              update({
+                max_width: e.target.value === "" ? null : Number(e.target.value),
+              })
+            }
+          />
+        </label>
+      </fieldset>
+
+      <fieldset>
+        <legend>Thresholds (normal / warning / critical)</legend>
+        {THRESHOLD_ROWS.map(([key, label]) => (
+          <label key={key}>
+            {label}
+            <NumberInput
+              value={config.thresholds[key].normal_lower_limit}
+              onChange={(v) => updateThreshold(key, "normal_lower_limit", v)}
+            />
+            <NumberInput
+              value={config.thresholds[key].warning_lower_limit}
+              onChange={(v) => updateThreshold(key, "warning_lower_limit", v)}
+            />
+            <NumberInput
+              value={config.thresholds[key].critical_lower_limit}
+              onChange={(v) => updateThreshold(key, "critical_lower_limit", v)}
+            />
+          </label>
+        ))}
+      </fieldset>
+
+      <fieldset>
+        <legend>Sampling</legend>
+        {SAMPLING_ROWS.map(([key, label]) => (
+          <label key={key}>
+            {label}
+            <NumberInput
+              value={config.sampling[key]}
+              onChange={(v) =>
+                update({ sampling: { ...config.sampling, [key]: v } })
+              }
+            />
+          </label>
+        ))}
+        <label>
+          Network targets
+          <input
+            value={targetsText}
+            onChange={(e) => {
+              setTargetsText(e.target.value);
+              update({
+                sampling: {
+                  ...config.sampling,
+                  nw_targets: e.target.value
+                    .split(",")
+                    .map((t) => t.trim())
+                    .filter((t) => t !== ""),
+                },
+              });
+            }}
+          />
+        </label>
+      </fieldset>
+
+      <div className="actions">
+        <button onClick={reset}>Reset to defaults</button>
+        <button onClick={save}>{saved ? "Saved" : "Save"}</button>
+      </div>
+    </main>
+  );
+}
+
+export default Settings;
@@ -0,0 +1,188 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, UnlistenFn } from "@tauri-apps/api/event";
+
+// Rust 側 (src-tauri/src/ipc) と対応する型
+
+export type AlertLevel = "Safe" | "Normal" | "Warning" | "Critical";
+
+export interface ProbeResult {
+  target: string;
+  ms: number | null;
+}
+
+export interface MetricsSnapshot {
+  cpu_pct: number | null;
+  mem_pressure_pct: number | null;
+  nw_ms: number | null;
+  nw_p95_ms: number | null;
+  nw_probes: ProbeResult[];
+  disk_used_pct: number | null;
+  procs: ProcessSample[];
+}
+
+// 見張っているプロセスごとの合計（動いていなければ count 0・値は null）
+export interface ProcessSample {
+  name: string;
+  count: number;
+  cpu_pct: number | null;
+  rss_bytes: number | null;
+}
+
+export interface Metric {
+  value: number;
+  level: AlertLevel;
+}
+
+// 履歴の傾きや複合ルールから決まるアラート（mem_rise / mem_exhaustion / disk_exhaustion / ルール名）
+export interface DerivedAlert {
+  name: string;
+  label: string;
+  value: number | null;
+  unit: string;
+  level: AlertLevel;
+}
+
+export interface ProcessMetric {
+  name: string;
+  count: number;
+  cpu: Metric | null;
+  rss_gb: Metric | null;
+}
+
+export interface ClassifiedSnapshot {
+  cpu: Metric | null;
+  mem: Metric | null;
+  nw: Metric | null;
+  nw_p95: number | null;
+  derived: DerivedAlert[];
+  procs: ProcessMetric[];
+}
+
+export interface Sample {
+  ts_ms: number;
+  snapshot: MetricsSnapshot;
+}
+
+export interface MetricsUpdate extends Sample {
+  classified: ClassifiedSnapshot;
+}
+
+// レベルの遷移（alerts::transitions）
+export interface Transition {
+  ts_ms: number;
+  metric: string;
+  label: string;
+  value: number | null;
+  unit: string;
+  previous: AlertLevel | null;
+  level: AlertLevel;
+}
+
+export type HistoryRange =
+  | { Last: number }
+  | { Between: { from_ms: number; to_ms: number } };
+
+export interface AlertThresholds {
+  normal_lower_limit: number;
+  warning_lower_limit: number;
+  critical_lower_limit: number;
+  // そのレベルになるのに続く必要のある秒数（省略時 0 = すぐ）
+  normal_for_secs?: number;
+  warning_for_secs?: number;
+  critical_for_secs?: number;
+}
+
+export interface SamplingConfig {
+  cpu_secs: number;
+  mem_secs: number;
+  nw_secs: number;
+  nw_timeout_secs: number;
+  nw_targets: string[];
+}
+
+export interface ProcessWatch {
+  name: string;
+  // プロセス名に対する正規表現（空なら name と同じ名前）
+  pattern: string;
+  cpu: AlertThresholds;
+  rss_gb: AlertThresholds;
+  show: boolean;
+}
+
+// 画面で扱わないフィールドもそのまま Rust に返す
+export interface TrayConfig {
+  version: number;
+  show_cpu: boolean;
+  show_mem: boolean;
+  show_nw: boolean;
+  mode: "List" | "Rotation" | "Summary";
+  is_alert: boolean;
+  rotation_secs: number;
+  pin_critical: boolean;
+  density: "UltraCompact" | "Compact" | "Verbose";
+  max_width: number | null;
+  thresholds: { cpu: AlertThresholds; mem: AlertThresholds; nw: AlertThresholds };
+  processes: ProcessWatch[];
+  sampling: SamplingConfig;
+  [key: string]: unknown;
+}
+
+export const getSnapshot = () => invoke<MetricsSnapshot>("get_snapshot");
+
+export const getClassifiedSnapshot = () =>
+  invoke<ClassifiedSnapshot>("get_classified_snapshot");
+
+export const getConfig = () => invoke<TrayConfig>("get_config");
+
+// 検証エラーのときは string[] で reject される
+export const setConfig = (config: TrayConfig) =>
+  invoke<void>("set_config", { config });
+
+export const getConfigIssues = () => invoke<string[]>("get_config_issues");
+
+export const getDefaultConfig = () => invoke<TrayConfig>("get_default_config");
+
+export const getHistory = (range: HistoryRange) =>
+  invoke<Sample[]>("get_history", { range });
+
+export const onMetricsUpdate = (
+  handler: (update: MetricsUpdate) => void,
+): Promise<UnlistenFn> =>
+  listen<MetricsUpdate>("metrics://update", (e) => handler(e.payload));
+
+export const listProfiles = () => invoke<string[]>("list_profiles");
+
+export const getActiveProfile = () =>
+  invoke<string | null>("get_active_profile");
+
+// 失敗したときは string[] で reject される
+export const switchProfile = (name: string) =>
+  invoke<void>("switch_profile", { name });
+
+export const exportProfile = (name: string) =>
+  invoke<string>("export_profile", { name });
+
+// 今の設定を選んだ場所に書き出す
+export const exportProfileFile = (path: string) =>
+  invoke<void>("export_profile_file", { path });
+
+export const importProfile = (path: string) =>
+  invoke<string>("import_profile", { path });
+
+// 書き出した行数を返す
+export const exportHistory = (minutes: number, path: string) =>
+  invoke<number>("export_history", { minutes, path });
+
+// 新しい順
+export const getAlertLog = (limit: number) =>
+  invoke<Transition[]>("get_alert_log", { limit });
+
+// metric を省略すると Warning 以上のものをすべて確認済みにする
+export const acknowledgeAlert = (metric?: string) =>
+  invoke<boolean>("acknowledge_alert", { metric: metric ?? null });
+
+export const clearAlertLog = () => invoke<void>("clear_alert_log");
+
+// untilMs は UNIX epoch ミリ秒。null で解除する
+export const setDoNotDisturb = (untilMs: number | null) =>
+  invoke<void>("set_do_not_disturb", { untilMs });
@@ -1,9 +1,13 @@
 import React from "react";
 import ReactDOM from "react-dom/client";
 import App from "./App";
+import Settings from "./Settings";
+
+// 設定ウィンドウは index.html#settings で開かれる
+const Root = window.location.hash === "#settings" ? Settings : App;
 
 ReactDOM.createRoot(document.getElementById("root") as HTMLElement).render(
   <React.StrictMode>
-    <App />
+    <Root />
   </React.StrictMode>,
 );